@@ -1,9 +1,17 @@
+use std::fmt;
+use std::time::Instant;
+
 use ratatui::widgets::ListState;
+use serde::Deserialize;
 
+use crate::breakpoint::Breakpoints;
+use crate::census::{Census, ObjectLabel};
 use crate::coordinates::Coordinates;
-use crate::grid::Grid;
+use crate::keymap::Keymap;
 use crate::pattern::PatternType;
+use crate::simulation::Simulation;
 use crate::size::Size;
+use crate::soup::Soup;
 use crate::viewport::Viewport;
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -11,6 +19,122 @@ pub enum AppMode {
     Normal,
     Help,
     PatternGallery,
+    Breakpoints,
+    /// Typing a value into the footer prompt
+    Prompt,
+}
+
+/// What a submitted prompt does with its input: the breakpoint actions add a
+/// breakpoint with the entered number
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum PromptAction {
+    PopulationAbove,
+    PopulationBelow,
+    GenerationReached,
+    /// Fast-forward to a generation (or `+N` generations ahead)
+    JumpToGeneration,
+    /// Run a `:` command line
+    Command,
+    /// Filter the pattern gallery as the input is typed
+    GallerySearch,
+    /// Clear the grid once `y` is pressed; any other key keeps it
+    ConfirmClear,
+}
+
+impl PromptAction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PromptAction::PopulationAbove => "break when population >",
+            PromptAction::PopulationBelow => "break when population <",
+            PromptAction::GenerationReached => "break at generation",
+            PromptAction::JumpToGeneration => "jump to generation (or +N)",
+            PromptAction::Command => ":",
+            PromptAction::GallerySearch => "/",
+            PromptAction::ConfirmClear => "clear the grid? (y/n)",
+        }
+    }
+}
+
+/// Progress of a headless fast-forward
+pub struct FastForward {
+    pub start: u64,
+    /// Generation the worker last reported reaching
+    pub generation: u64,
+    pub target: u64,
+    pub started: Instant,
+}
+
+/// A single-line input shown on the footer
+pub struct Prompt {
+    pub action: PromptAction,
+    pub input: String,
+    /// Mode to go back to once the prompt is submitted or cancelled
+    pub return_mode: AppMode,
+    /// Entry of the command history being shown, while browsing it
+    pub history_index: Option<usize>,
+    /// Candidates offered by tab completion, and the one shown
+    pub completions: Vec<String>,
+    pub completion: usize,
+}
+
+impl Prompt {
+    pub fn new(action: PromptAction, return_mode: AppMode) -> Self {
+        Self {
+            action,
+            input: String::new(),
+            return_mode,
+            history_index: None,
+            completions: Vec::new(),
+            completion: 0,
+        }
+    }
+}
+
+/// Which panel is shown to the right of the canvas
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SidePanel {
+    Gallery,
+    Census,
+    Breakpoints,
+}
+
+/// How the canvas colours cells in the life engine
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DisplayMode {
+    /// Plain live and dead cells
+    Cells,
+    /// Live cells coloured by how long they have been alive
+    Age,
+    /// Recently dead cells fade out behind moving objects
+    Trails,
+    /// How often each cell has been alive since the grid was created
+    Heatmap,
+    /// Cells born and cells that died in the last generation
+    Diff,
+}
+
+impl DisplayMode {
+    pub fn next(self) -> Self {
+        match self {
+            DisplayMode::Cells => DisplayMode::Age,
+            DisplayMode::Age => DisplayMode::Trails,
+            DisplayMode::Trails => DisplayMode::Heatmap,
+            DisplayMode::Heatmap => DisplayMode::Diff,
+            DisplayMode::Diff => DisplayMode::Cells,
+        }
+    }
+}
+
+impl fmt::Display for DisplayMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DisplayMode::Cells => "cells",
+            DisplayMode::Age => "age",
+            DisplayMode::Trails => "trails",
+            DisplayMode::Heatmap => "heatmap",
+            DisplayMode::Diff => "diff",
+        })
+    }
 }
 
 /// Tracks the gallery navigation state
@@ -35,7 +159,7 @@ impl GalleryCursor {
 }
 
 pub struct App {
-    pub grid: Grid,
+    pub simulation: Simulation,
     pub cursor: Coordinates,
     pub viewport: Viewport,
     pub viewport_size: Size,
@@ -44,8 +168,43 @@ pub struct App {
     pub current_pattern_type: usize,
     pub last_pattern: Option<usize>,
     pub simulation_delay: u128,
+    /// Generations computed each time the delay elapses
+    pub gens_per_frame: u32,
+    /// Measured simulation and redraw rates
+    pub gens_per_second: f64,
+    pub frames_per_second: f64,
+    /// Pause the run loop as soon as the universe becomes periodic
+    pub pause_when_stable: bool,
     pub mode: AppMode,
     pub gallery_cursor: GalleryCursor,
+    pub side_panel: SidePanel,
+    pub census: Census,
+    pub display_mode: DisplayMode,
+    /// Show the population chart beneath the canvas
+    pub show_chart: bool,
+    /// Outcome of the last command worth reporting, shown on the footer
+    pub message: Option<String>,
+    pub breakpoints: Breakpoints,
+    pub prompt: Option<Prompt>,
+    pub fast_forward: Option<FastForward>,
+    /// Label recognised gallery objects on the canvas
+    pub show_labels: bool,
+    pub labels: Vec<ObjectLabel>,
+    /// Generation and grid revision the labels were found for
+    pub labelled: Option<(u64, u64)>,
+    /// Corner of the rectangular selection; the cursor is the opposite corner
+    pub selection_anchor: Option<Coordinates>,
+    /// Settings and seed of the most recent soup, so it can be regenerated
+    pub soup: Soup,
+    /// Side of the square soup placed at the cursor when nothing is selected
+    pub soup_size: usize,
+    /// Active key bindings, also listed by the help popup
+    pub keymap: Keymap,
+    /// Count and keys typed so far of an unfinished command, shown on the
+    /// footer
+    pub pending_keys: String,
+    /// Command lines run from the `:` prompt, oldest first
+    pub command_history: Vec<String>,
 }
 
 impl App {
@@ -71,7 +230,59 @@ impl App {
             .unwrap_or("unknown")
     }
 
+    /// Recompute the object census from the current grid
+    pub fn take_census(&mut self) {
+        self.census.take(
+            &self.simulation.grid,
+            &self.simulation.rule,
+            &self.configuration,
+        );
+    }
+
+    /// Recompute the object labels when the overlay is shown and the grid
+    /// has moved on since they were found. Jumps hide them until they end.
+    pub fn refresh_labels(&mut self) {
+        if !self.show_labels || self.fast_forward.is_some() {
+            self.labels.clear();
+            self.labelled = None;
+            return;
+        }
+        let state = (self.simulation.generation, self.simulation.grid.revision());
+        if self.labelled != Some(state) {
+            self.labels = self.census.recognize(
+                &self.simulation.grid,
+                &self.simulation.rule,
+                &self.configuration,
+            );
+            self.labelled = Some(state);
+        }
+    }
+
+    /// What the pattern gallery is filtered by, while searching it
+    pub fn gallery_search(&self) -> Option<&str> {
+        self.prompt
+            .as_ref()
+            .filter(|prompt| prompt.action == PromptAction::GallerySearch)
+            .map(|prompt| prompt.input.as_str())
+    }
+
     pub fn grid_cursor(&self) -> Coordinates {
         self.viewport.view_to_grid(self.cursor.clone())
     }
+
+    /// Top-left corner and size of the selection, in grid coordinates
+    pub fn selection(&self) -> Option<(Coordinates, Size)> {
+        let anchor = self.selection_anchor.as_ref()?;
+        let cursor = self.grid_cursor();
+        Some((
+            Coordinates {
+                x: anchor.x.min(cursor.x),
+                y: anchor.y.min(cursor.y),
+            },
+            Size {
+                width: anchor.x.abs_diff(cursor.x) + 1,
+                height: anchor.y.abs_diff(cursor.y) + 1,
+            },
+        ))
+    }
 }
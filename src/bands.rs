@@ -0,0 +1,56 @@
+use std::ops::Range;
+use std::thread;
+
+/// Run `work` on consecutive chunks of `items`, passing each chunk's index.
+/// Chunks run on their own threads when there is more than one.
+pub fn for_each_band<T, F>(items: &mut [T], chunk_len: usize, work: F)
+where
+    T: Send,
+    F: Fn(usize, &mut [T]) + Sync,
+{
+    let chunk_len = chunk_len.max(1);
+    if items.len() <= chunk_len {
+        work(0, items);
+        return;
+    }
+
+    let work = &work;
+    thread::scope(|scope| {
+        for (index, chunk) in items.chunks_mut(chunk_len).enumerate() {
+            scope.spawn(move || work(index, chunk));
+        }
+    });
+}
+
+/// Run `work` on consecutive bands of `band_rows` rows out of `height`,
+/// returning the results in row order
+pub fn map_bands<R, F>(height: usize, band_rows: usize, work: F) -> Vec<R>
+where
+    R: Send,
+    F: Fn(Range<usize>) -> R + Sync,
+{
+    let band_rows = band_rows.max(1);
+    let bands: Vec<Range<usize>> = (0..height)
+        .step_by(band_rows)
+        .map(|start| start..(start + band_rows).min(height))
+        .collect();
+    if bands.len() <= 1 {
+        return vec![work(0..height)];
+    }
+
+    let work = &work;
+    thread::scope(|scope| {
+        let handles: Vec<_> = bands
+            .into_iter()
+            .map(|rows| scope.spawn(move || work(rows)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e))
+            })
+            .collect()
+    })
+}
@@ -0,0 +1,284 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::thread;
+use std::time::Instant;
+
+use serde::Serialize;
+
+use crate::coordinates::Coordinates;
+use crate::grid::{Backend, Grid};
+use crate::health::Health;
+use crate::lenia::LeniaParams;
+use crate::margolus::MargolusRule;
+use crate::pattern::PatternType;
+use crate::rule::LifeRule;
+use crate::simulation::{Engine, Simulation, SimulationConfig};
+use crate::size::Size;
+use crate::soup::{Soup, Symmetry};
+use crate::turmite::TurmiteRule;
+
+/// Gallery patterns run on the life engine, by name
+const LIFE_PATTERNS: [&str; 3] = ["acorn", "r-pentomino", "gosper glider gun"];
+
+/// Options for `rustmaton bench`
+#[derive(clap::Args, Debug)]
+pub struct BenchArgs {
+    /// grid sizes to measure, as comma-separated WIDTHxHEIGHT
+    #[arg(long, value_delimiter = ',', default_value = "256x256,1024x1024")]
+    sizes: Vec<Size>,
+
+    /// seconds to run each engine, workload and size for
+    #[arg(long, default_value_t = 1.0)]
+    seconds: f64,
+
+    /// life-like rule in B/S notation
+    #[arg(long, default_value = "B3/S23")]
+    rule: LifeRule,
+
+    /// threads each life generation may be split across (defaults to one per CPU core)
+    #[arg(long)]
+    threads: Option<usize>,
+
+    /// soup symmetry in apgsearch notation, e.g. C1, C2_4, D4_+1, D8_1
+    #[arg(long, default_value = "C1")]
+    symmetry: Symmetry,
+
+    /// fraction of soup cells that start alive
+    #[arg(long, default_value_t = 0.5)]
+    soup_density: f64,
+
+    /// Wolfram rule (0-255) used by the elementary 1D engine
+    #[arg(long, default_value_t = 30)]
+    elementary_rule: u8,
+
+    /// turmite rule, one turn (L, R, N, U) per cell colour, e.g. RL or LLRR
+    #[arg(long, default_value = "RL")]
+    turmite_rule: TurmiteRule,
+
+    /// margolus block rule: critters, bbm, tron, or 16 comma-separated block states
+    #[arg(long, default_value = "critters")]
+    margolus_rule: MargolusRule,
+
+    /// seed for soups and randomly seeded engines
+    #[arg(long, default_value_t = 1)]
+    seed: u64,
+
+    /// file the results are written to as JSON
+    #[arg(long, default_value = "bench.json")]
+    json: String,
+}
+
+/// How a benchmark grid is populated before timing starts
+enum Workload {
+    /// A gallery pattern in the middle of the grid
+    Pattern(Vec<Vec<Health>>),
+    /// A random soup covering the whole grid
+    Soup,
+    /// The engine's own random seeding
+    Random,
+    /// The engine's own single seed in the middle of the grid
+    Single,
+}
+
+#[derive(Serialize)]
+struct Measurement {
+    engine: String,
+    workload: String,
+    width: usize,
+    height: usize,
+    generations: u64,
+    seconds: f64,
+    gens_per_second: f64,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    rule: String,
+    threads: usize,
+    seed: u64,
+    results: &'a [Measurement],
+}
+
+/// Time every engine on each grid size, printing a table and writing JSON
+pub fn run(args: BenchArgs, configuration: &[PatternType]) -> io::Result<()> {
+    let threads = args
+        .threads
+        .or_else(|| thread::available_parallelism().ok().map(|n| n.get()))
+        .unwrap_or(1)
+        .max(1);
+
+    let mut cases: Vec<(String, Engine, Backend, String, Workload)> = Vec::new();
+    for backend in [Backend::Packed, Backend::Scalar] {
+        let engine = format!("life {backend}");
+        for name in LIFE_PATTERNS {
+            match configuration
+                .iter()
+                .flat_map(|pt| pt.patterns.iter())
+                .find(|p| p.name == name)
+            {
+                Some(pattern) => cases.push((
+                    engine.clone(),
+                    Engine::Life,
+                    backend,
+                    name.to_string(),
+                    Workload::Pattern(pattern.matrix.clone()),
+                )),
+                None => eprintln!("Warning: pattern '{name}' not found, skipping it"),
+            }
+        }
+        cases.push((
+            engine,
+            Engine::Life,
+            backend,
+            String::from("soup"),
+            Workload::Soup,
+        ));
+    }
+    cases.push((
+        String::from("elementary"),
+        Engine::Elementary,
+        Backend::Packed,
+        String::from("random row"),
+        Workload::Random,
+    ));
+    cases.push((
+        String::from("turmite"),
+        Engine::Turmite,
+        Backend::Packed,
+        String::from("ant"),
+        Workload::Single,
+    ));
+    cases.push((
+        String::from("margolus"),
+        Engine::Margolus,
+        Backend::Packed,
+        String::from("soup"),
+        Workload::Soup,
+    ));
+    cases.push((
+        String::from("lenia"),
+        Engine::Lenia,
+        Backend::Packed,
+        String::from("random"),
+        Workload::Random,
+    ));
+
+    eprintln!(
+        "benchmarking {} with {} threads, {}s per case",
+        args.rule, threads, args.seconds
+    );
+    println!(
+        "{:<12} {:<18} {:>11} {:>10} {:>8} {:>12}",
+        "engine", "workload", "size", "gens", "secs", "gens/s"
+    );
+
+    let mut results = Vec::new();
+    for size in &args.sizes {
+        for (label, engine, backend, workload_name, workload) in &cases {
+            let mut simulation = prepare(&args, size, *engine, *backend, threads, workload);
+            let started = Instant::now();
+            let mut generations = 0;
+            // Always at least one generation, however slow
+            while generations == 0 || started.elapsed().as_secs_f64() < args.seconds {
+                simulation.step();
+                generations += 1;
+            }
+            let seconds = started.elapsed().as_secs_f64();
+            let measurement = Measurement {
+                engine: label.clone(),
+                workload: workload_name.clone(),
+                width: size.width,
+                height: size.height,
+                generations,
+                seconds,
+                gens_per_second: generations as f64 / seconds,
+            };
+            println!(
+                "{:<12} {:<18} {:>11} {:>10} {:>8.2} {:>12.1}",
+                measurement.engine,
+                measurement.workload,
+                format!("{}x{}", size.width, size.height),
+                measurement.generations,
+                measurement.seconds,
+                measurement.gens_per_second
+            );
+            results.push(measurement);
+        }
+    }
+
+    let report = Report {
+        rule: args.rule.to_string(),
+        threads,
+        seed: args.seed,
+        results: &results,
+    };
+    let mut out = BufWriter::new(File::create(&args.json)?);
+    serde_json::to_writer_pretty(&mut out, &report).map_err(io::Error::other)?;
+    writeln!(out)?;
+    out.flush()?;
+    eprintln!("wrote {} results to {}", results.len(), args.json);
+    Ok(())
+}
+
+/// A fresh simulation of one engine, populated for the workload
+fn prepare(
+    args: &BenchArgs,
+    size: &Size,
+    engine: Engine,
+    backend: Backend,
+    threads: usize,
+    workload: &Workload,
+) -> Simulation {
+    let grid = Grid::new(size.clone());
+    let config = SimulationConfig {
+        rule: args.rule.clone(),
+        seed: args.seed,
+        noise_rate: 0.0,
+        elementary_rule: args.elementary_rule,
+        turmite_rule: args.turmite_rule.clone(),
+        margolus_rule: args.margolus_rule.clone(),
+        // The viewer's default parameters
+        lenia: LeniaParams {
+            radius: 13,
+            mu: 0.15,
+            sigma: 0.02,
+            dt: 0.1,
+        },
+        backend,
+        threads,
+    };
+    let mut simulation = Simulation::new(grid, config);
+    while simulation.engine != engine {
+        simulation.cycle_engine();
+    }
+    simulation
+        .elementary
+        .set_window(0, simulation.grid.get_size().height);
+
+    let centre = Coordinates {
+        x: size.width / 2,
+        y: size.height / 2,
+    };
+    match workload {
+        Workload::Pattern(matrix) => {
+            let width = matrix.first().map(|row| row.len()).unwrap_or(0);
+            let position = Coordinates {
+                x: centre.x.saturating_sub(width / 2),
+                y: centre.y.saturating_sub(matrix.len() / 2),
+            };
+            simulation.grid.shape(position, matrix);
+        }
+        Workload::Soup => {
+            let soup = Soup {
+                symmetry: args.symmetry,
+                density: args.soup_density,
+                seed: args.seed,
+            };
+            let cells = soup.generate(size.width, size.height);
+            simulation.grid.shape(Coordinates { x: 0, y: 0 }, &cells);
+        }
+        Workload::Random => simulation.seed_random(),
+        Workload::Single => simulation.seed_single(centre),
+    }
+    simulation
+}
@@ -0,0 +1,274 @@
+use std::fmt;
+
+use crate::coordinates::Coordinates;
+use crate::grid::Grid;
+use crate::health::Health;
+use crate::simulation::Simulation;
+use crate::size::Size;
+
+/// When a breakpoint pauses the run loop
+#[derive(Clone, Debug)]
+pub enum Condition {
+    PopulationAbove(usize),
+    PopulationBelow(usize),
+    /// Any cell inside the rectangle changes
+    RegionChanged {
+        position: Coordinates,
+        size: Size,
+    },
+    Generation(u64),
+    /// The universe becomes periodic
+    Periodic,
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::PopulationAbove(n) => write!(f, "population > {n}"),
+            Condition::PopulationBelow(n) => write!(f, "population < {n}"),
+            Condition::RegionChanged { position, size } => {
+                write!(f, "change in {size} at {},{}", position.x, position.y)
+            }
+            Condition::Generation(n) => write!(f, "generation {n}"),
+            Condition::Periodic => f.write_str("periodic"),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Breakpoint {
+    /// Stays the same as other breakpoints are added and removed
+    pub id: u64,
+    pub condition: Condition,
+    pub enabled: bool,
+    /// Whether the condition held at the last check, so a breakpoint fires
+    /// when its condition becomes true rather than on every generation
+    held: bool,
+    /// Cells of a watched rectangle at the last check
+    region: Vec<Health>,
+}
+
+impl Breakpoint {
+    /// A breakpoint whose condition already holds only fires once it has
+    /// stopped holding and holds again
+    pub fn new(id: u64, condition: Condition, simulation: &Simulation) -> Self {
+        let mut breakpoint = Self {
+            id,
+            condition,
+            enabled: true,
+            held: false,
+            region: Vec::new(),
+        };
+        breakpoint.region = breakpoint.watched_cells(&simulation.grid);
+        breakpoint.held = breakpoint.holds(simulation);
+        breakpoint
+    }
+
+    fn holds(&self, simulation: &Simulation) -> bool {
+        let population = simulation.grid.population();
+        match &self.condition {
+            Condition::PopulationAbove(n) => population > *n,
+            Condition::PopulationBelow(n) => population < *n,
+            Condition::Generation(n) => simulation.generation >= *n,
+            Condition::Periodic => simulation.period.stability().is_some(),
+            Condition::RegionChanged { .. } => false,
+        }
+    }
+
+    fn watched_cells(&self, grid: &Grid) -> Vec<Health> {
+        match &self.condition {
+            Condition::RegionChanged { position, size } => (position.y..position.y + size.height)
+                .flat_map(|y| {
+                    (position.x..position.x + size.width)
+                        .map(move |x| grid.get_cell(Coordinates { x, y }).unwrap_or(Health::Dead))
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Evaluate the condition against the simulation; true if it fires
+    fn check(&mut self, simulation: &Simulation) -> bool {
+        if let Condition::RegionChanged { .. } = self.condition {
+            let cells = self.watched_cells(&simulation.grid);
+            let changed = cells != self.region;
+            self.region = cells;
+            // Every change fires, not just the first
+            return self.enabled && changed;
+        }
+
+        let holds = self.holds(simulation);
+        let fires = holds && !self.held;
+        self.held = holds;
+        self.enabled && fires
+    }
+}
+
+/// Debugger-style conditions that pause the run loop
+#[derive(Clone, Default)]
+pub struct Breakpoints {
+    pub list: Vec<Breakpoint>,
+    /// Highlighted entry in the breakpoints panel
+    pub selected: usize,
+    /// Id of the breakpoint that last paused the run, until the run resumes
+    pub fired: Option<u64>,
+    next_id: u64,
+}
+
+impl Breakpoints {
+    pub fn add(&mut self, condition: Condition, simulation: &Simulation) {
+        self.list
+            .push(Breakpoint::new(self.next_id, condition, simulation));
+        self.next_id += 1;
+        self.selected = self.list.len() - 1;
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.list.len() {
+            self.list.remove(index);
+            self.selected = self.selected.min(self.list.len().saturating_sub(1));
+        }
+    }
+
+    pub fn toggle(&mut self, index: usize) {
+        if let Some(breakpoint) = self.list.get_mut(index) {
+            breakpoint.enabled = !breakpoint.enabled;
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.list.len() {
+            self.selected += 1;
+        }
+    }
+
+    /// The breakpoint that last paused the run, if it still exists
+    pub fn fired(&self) -> Option<&Breakpoint> {
+        let id = self.fired?;
+        self.list.iter().find(|breakpoint| breakpoint.id == id)
+    }
+
+    /// Check every breakpoint after a generation; returns the id of the
+    /// first that fired
+    pub fn check(&mut self, simulation: &Simulation) -> Option<u64> {
+        let mut fired = None;
+        for breakpoint in &mut self.list {
+            if breakpoint.check(simulation) && fired.is_none() {
+                fired = Some(breakpoint.id);
+            }
+        }
+        if fired.is_some() {
+            self.fired = fired;
+        }
+        fired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Backend;
+    use crate::lenia::LeniaParams;
+    use crate::simulation::SimulationConfig;
+
+    fn empty_simulation() -> Simulation {
+        let grid = Grid::new(Size {
+            width: 8,
+            height: 8,
+        });
+        Simulation::new(
+            grid,
+            SimulationConfig {
+                rule: "B3/S23".parse().unwrap(),
+                seed: 0,
+                noise_rate: 0.0,
+                elementary_rule: 30,
+                turmite_rule: "RL".parse().unwrap(),
+                margolus_rule: "critters".parse().unwrap(),
+                lenia: LeniaParams {
+                    radius: 13,
+                    mu: 0.15,
+                    sigma: 0.02,
+                    dt: 0.1,
+                },
+                backend: Backend::Scalar,
+                threads: 1,
+            },
+        )
+    }
+
+    /// Set the population to `n` cells along the top rows
+    fn populate(simulation: &mut Simulation, n: usize) {
+        for index in 0..64 {
+            let cell = Coordinates {
+                x: index % 8,
+                y: index / 8,
+            };
+            if index < n {
+                simulation.grid.resurrect(cell);
+            } else {
+                simulation.grid.kill(cell);
+            }
+        }
+    }
+
+    /// Ids returned by `check` after setting each population in turn
+    fn fired_over(breakpoints: &mut Breakpoints, populations: &[usize]) -> Vec<Option<u64>> {
+        let mut simulation = empty_simulation();
+        populations
+            .iter()
+            .map(|n| {
+                populate(&mut simulation, *n);
+                breakpoints.check(&simulation)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn population_breakpoints_fire_on_crossing_the_threshold() {
+        let mut breakpoints = Breakpoints::default();
+        breakpoints.add(Condition::PopulationAbove(3), &empty_simulation());
+        let id = breakpoints.list[0].id;
+
+        assert_eq!(
+            fired_over(&mut breakpoints, &[2, 4, 5, 9, 3, 1, 6, 7]),
+            vec![None, Some(id), None, None, None, None, Some(id), None]
+        );
+        assert_eq!(breakpoints.fired().map(|b| b.id), Some(id));
+    }
+
+    #[test]
+    fn disabled_breakpoints_never_fire() {
+        let mut breakpoints = Breakpoints::default();
+        breakpoints.add(Condition::PopulationAbove(3), &empty_simulation());
+        breakpoints.toggle(0);
+
+        assert_eq!(
+            fired_over(&mut breakpoints, &[2, 4, 5, 1, 6]),
+            vec![None; 5]
+        );
+        assert!(breakpoints.fired().is_none());
+    }
+
+    #[test]
+    fn generation_breakpoints_fire_at_the_target() {
+        let mut simulation = empty_simulation();
+        let mut breakpoints = Breakpoints::default();
+        breakpoints.add(Condition::Generation(5), &simulation);
+        let id = breakpoints.list[0].id;
+
+        let mut fired = Vec::new();
+        for _ in 0..8 {
+            simulation.step();
+            if breakpoints.check(&simulation).is_some() {
+                fired.push(simulation.generation);
+            }
+        }
+        assert_eq!(fired, vec![5]);
+        assert_eq!(breakpoints.fired().map(|b| b.id), Some(id));
+    }
+}
@@ -0,0 +1,398 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+
+use crate::coordinates::Coordinates;
+use crate::grid::Grid;
+use crate::health::Health::Alive;
+use crate::pattern::PatternType;
+use crate::period::{PeriodDetector, Stability};
+use crate::rng::Rng;
+use crate::rule::LifeRule;
+use crate::size::Size;
+
+/// Generations an isolated object is simulated for when classifying it
+const CLASSIFY_GENERATIONS: u64 = 64;
+/// Empty border around an isolated object, enough for a c/2 ship to stay inside
+const CLASSIFY_MARGIN: usize = CLASSIFY_GENERATIONS as usize / 2 + 2;
+
+/// Classified islands remembered before the cache is discarded, so chaotic
+/// soups do not fill it with every transient they pass through
+pub const MAX_CLASSIFIED: usize = 10_000;
+
+/// Digits of the extended Wechsler format
+const WECHSLER_DIGITS: &[u8; 32] = b"0123456789abcdefghijklmnopqrstuv";
+
+/// What an object turned out to be when simulated on its own
+#[derive(Clone, Debug, PartialEq)]
+pub enum ObjectKind {
+    StillLife,
+    Oscillator(u64),
+    Spaceship(u64, String),
+    /// Did not settle into a cycle within the classification window
+    Unstable,
+}
+
+impl fmt::Display for ObjectKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjectKind::StillLife => f.write_str("still"),
+            ObjectKind::Oscillator(period) => write!(f, "p{period}"),
+            ObjectKind::Spaceship(_, speed) => f.write_str(speed),
+            ObjectKind::Unstable => f.write_str("unstable"),
+        }
+    }
+}
+
+/// A classified object: its kind and apgsearch-style code
+#[derive(Clone, Debug)]
+pub struct Classification {
+    pub kind: ObjectKind,
+    pub apgcode: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct CensusEntry {
+    /// Name from the pattern gallery, if the object is a known pattern
+    pub name: Option<String>,
+    pub classification: Classification,
+    pub count: usize,
+}
+
+impl CensusEntry {
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.classification.apgcode)
+    }
+}
+
+/// A gallery object found on the grid, with its bounding box
+#[derive(Clone, Debug)]
+pub struct ObjectLabel {
+    pub name: String,
+    pub position: Coordinates,
+    pub size: Size,
+}
+
+/// Census of the objects on the grid, in the spirit of apgsearch.
+///
+/// Live cells are split into 8-connected islands, each island is canonicalised
+/// over rotations and reflections, simulated alone to classify it, and named
+/// from the pattern gallery when it matches.
+#[derive(Clone, Default)]
+pub struct Census {
+    pub entries: Vec<CensusEntry>,
+    pub total: usize,
+    classified: HashMap<String, Classification>,
+    known: HashMap<String, String>,
+    rule: Option<LifeRule>,
+}
+
+impl Census {
+    /// Recompute the census for the current grid
+    pub fn take(&mut self, grid: &Grid, rule: &LifeRule, configuration: &[PatternType]) {
+        self.prepare(rule, configuration);
+
+        let mut counts: HashMap<String, (Classification, usize)> = HashMap::new();
+        let islands = islands(grid);
+        self.total = islands.len();
+
+        for island in islands {
+            let classification = self.classify_cached(&island, rule);
+            counts
+                .entry(classification.apgcode.clone())
+                .or_insert((classification, 0))
+                .1 += 1;
+        }
+
+        self.entries = counts
+            .into_values()
+            .map(|(classification, count)| CensusEntry {
+                name: self.known.get(&classification.apgcode).cloned(),
+                classification,
+                count,
+            })
+            .collect();
+        self.entries
+            .sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.label().cmp(b.label())));
+    }
+
+    /// Find the gallery objects on the grid and their bounding boxes
+    pub fn recognize(
+        &mut self,
+        grid: &Grid,
+        rule: &LifeRule,
+        configuration: &[PatternType],
+    ) -> Vec<ObjectLabel> {
+        self.prepare(rule, configuration);
+
+        let mut labels = Vec::new();
+        for island in islands(grid) {
+            let classification = self.classify_cached(&island, rule);
+            if let Some(name) = self.known.get(&classification.apgcode) {
+                let min_x = island.iter().map(|(x, _)| *x).min().unwrap_or(0);
+                let min_y = island.iter().map(|(_, y)| *y).min().unwrap_or(0);
+                let max_x = island.iter().map(|(x, _)| *x).max().unwrap_or(0);
+                let max_y = island.iter().map(|(_, y)| *y).max().unwrap_or(0);
+                labels.push(ObjectLabel {
+                    name: name.clone(),
+                    position: Coordinates { x: min_x, y: min_y },
+                    size: Size {
+                        width: max_x - min_x + 1,
+                        height: max_y - min_y + 1,
+                    },
+                });
+            }
+        }
+        labels
+    }
+
+    /// Reset the caches when the rule changes
+    fn prepare(&mut self, rule: &LifeRule, configuration: &[PatternType]) {
+        if self.rule.as_ref() != Some(rule) {
+            self.classified.clear();
+            self.known = known_objects(rule, configuration);
+            self.rule = Some(rule.clone());
+        }
+    }
+
+    fn classify_cached(&mut self, island: &[(usize, usize)], rule: &LifeRule) -> Classification {
+        if self.classified.len() >= MAX_CLASSIFIED {
+            self.classified.clear();
+        }
+        self.classified
+            .entry(canonical_code(island))
+            .or_insert_with(|| classify(island, rule))
+            .clone()
+    }
+}
+
+/// apgcodes of the named gallery patterns that are stable objects
+pub fn known_objects(rule: &LifeRule, configuration: &[PatternType]) -> HashMap<String, String> {
+    let mut known = HashMap::new();
+    for pattern in configuration.iter().flat_map(|pt| pt.patterns.iter()) {
+        let cells: Vec<(usize, usize)> = pattern
+            .matrix
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, cell)| **cell == Alive)
+                    .map(move |(x, _)| (x, y))
+            })
+            .collect();
+        if cells.is_empty() {
+            continue;
+        }
+        let classification = classify(&cells, rule);
+        if classification.kind != ObjectKind::Unstable {
+            known
+                .entry(classification.apgcode)
+                .or_insert_with(|| pattern.name.clone());
+        }
+    }
+    known
+}
+
+/// Split the live cells of a grid into 8-connected islands
+pub fn islands(grid: &Grid) -> Vec<Vec<(usize, usize)>> {
+    let mut remaining: HashSet<(usize, usize)> = grid.live_cells().collect();
+    let mut islands = Vec::new();
+
+    while let Some(&start) = remaining.iter().next() {
+        remaining.remove(&start);
+        let mut island = vec![start];
+        let mut queue = VecDeque::from([start]);
+
+        while let Some((x, y)) = queue.pop_front() {
+            for ny in y.saturating_sub(1)..=y + 1 {
+                for nx in x.saturating_sub(1)..=x + 1 {
+                    if remaining.remove(&(nx, ny)) {
+                        island.push((nx, ny));
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+        }
+        islands.push(island);
+    }
+
+    islands
+}
+
+/// Shift cells so the bounding box starts at the origin
+fn normalize(cells: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let min_x = cells.iter().map(|(x, _)| *x).min().unwrap_or(0);
+    let min_y = cells.iter().map(|(_, y)| *y).min().unwrap_or(0);
+    cells.iter().map(|(x, y)| (x - min_x, y - min_y)).collect()
+}
+
+/// Maps (x, y) within a bounding box of (max x, max y) to a new position
+type Transform = fn(usize, usize, usize, usize) -> (usize, usize);
+
+/// The 8 rotations and reflections of a normalised cell set
+fn orientations(cells: &[(usize, usize)]) -> Vec<Vec<(usize, usize)>> {
+    let w = cells.iter().map(|(x, _)| *x).max().unwrap_or(0);
+    let h = cells.iter().map(|(_, y)| *y).max().unwrap_or(0);
+    let transforms: [Transform; 8] = [
+        |x, y, _, _| (x, y),
+        |x, y, w, _| (w - x, y),
+        |x, y, _, h| (x, h - y),
+        |x, y, w, h| (w - x, h - y),
+        |x, y, _, _| (y, x),
+        |x, y, _, h| (h - y, x),
+        |x, y, w, _| (y, w - x),
+        |x, y, w, h| (h - y, w - x),
+    ];
+    transforms
+        .iter()
+        .map(|t| cells.iter().map(|(x, y)| t(*x, *y, w, h)).collect())
+        .collect()
+}
+
+/// Encode a normalised cell set in the extended Wechsler format used by apgcodes
+fn wechsler(cells: &[(usize, usize)]) -> String {
+    let width = cells.iter().map(|(x, _)| x + 1).max().unwrap_or(0);
+    let height = cells.iter().map(|(_, y)| y + 1).max().unwrap_or(0);
+    let alive: HashSet<&(usize, usize)> = cells.iter().collect();
+
+    let mut strips = Vec::new();
+    for strip in 0..height.div_ceil(5) {
+        let mut encoded = String::new();
+        let mut zeros = 0;
+        for x in 0..width {
+            let value = (0..5)
+                .filter(|row| alive.contains(&(x, strip * 5 + row)))
+                .fold(0, |acc, row| acc | (1 << row));
+            if value == 0 {
+                zeros += 1;
+            } else {
+                push_zeros(&mut encoded, zeros);
+                zeros = 0;
+                encoded.push(WECHSLER_DIGITS[value] as char);
+            }
+        }
+        strips.push(encoded);
+    }
+
+    strips.join("z")
+}
+
+fn push_zeros(encoded: &mut String, mut zeros: usize) {
+    while zeros > 0 {
+        match zeros {
+            1 => encoded.push('0'),
+            2 => encoded.push('w'),
+            3 => encoded.push('x'),
+            _ => {
+                let run = zeros.min(39);
+                encoded.push('y');
+                encoded.push(WECHSLER_DIGITS[run - 4] as char);
+                zeros -= run;
+                continue;
+            }
+        }
+        return;
+    }
+}
+
+/// Shortest (then lexicographically smallest) encoding over all orientations
+pub fn canonical_code(cells: &[(usize, usize)]) -> String {
+    orientations(&normalize(cells))
+        .iter()
+        .map(|orientation| wechsler(orientation))
+        .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+        .unwrap_or_default()
+}
+
+/// Simulate an object on its own and classify it
+pub fn classify(cells: &[(usize, usize)], rule: &LifeRule) -> Classification {
+    let cells = normalize(cells);
+    let width = cells.iter().map(|(x, _)| x + 1).max().unwrap_or(0);
+    let height = cells.iter().map(|(_, y)| y + 1).max().unwrap_or(0);
+
+    let mut grid = Grid::new(Size {
+        width: width + 2 * CLASSIFY_MARGIN,
+        height: height + 2 * CLASSIFY_MARGIN,
+    });
+    for (x, y) in &cells {
+        grid.resurrect(Coordinates {
+            x: x + CLASSIFY_MARGIN,
+            y: y + CLASSIFY_MARGIN,
+        });
+    }
+
+    let mut rng = Rng::new(0);
+    let mut detector = PeriodDetector::default();
+    let mut phases = vec![cells.clone()];
+    detector.sync(&grid, 0);
+
+    let mut stability: Option<Stability> = None;
+    for generation in 1..=CLASSIFY_GENERATIONS {
+        grid.generate(rule, &mut rng);
+        detector.observe(&grid, generation);
+        if let Some(found) = detector.stability() {
+            stability = Some(found);
+            break;
+        }
+        phases.push(grid.live_cells().collect());
+    }
+
+    let kind = match stability {
+        Some(s) if s.since == 0 => match s.speed() {
+            Some(speed) => ObjectKind::Spaceship(s.period, speed),
+            None if s.period == 1 => ObjectKind::StillLife,
+            None => ObjectKind::Oscillator(s.period),
+        },
+        _ => ObjectKind::Unstable,
+    };
+
+    let prefix = match &kind {
+        ObjectKind::StillLife => format!("xs{}", cells.len()),
+        ObjectKind::Oscillator(period) => format!("xp{period}"),
+        ObjectKind::Spaceship(period, _) => format!("xq{period}"),
+        ObjectKind::Unstable => String::from("zz"),
+    };
+
+    // Periodic objects use the smallest code over all of their phases
+    let phases = match stability {
+        Some(s) if s.since == 0 => &phases[..s.period as usize],
+        _ => &phases[..1],
+    };
+    let code = phases
+        .iter()
+        .filter(|phase| !phase.is_empty())
+        .map(|phase| canonical_code(phase))
+        .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+        .unwrap_or_default();
+
+    Classification {
+        kind,
+        apgcode: format!("{prefix}_{code}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_code_in_every_orientation(cells: &[(usize, usize)], apgcode: &str) {
+        let rule: LifeRule = "B3/S23".parse().unwrap();
+        for (index, orientation) in orientations(cells).iter().enumerate() {
+            assert_eq!(
+                classify(orientation, &rule).apgcode,
+                apgcode,
+                "orientation {index}"
+            );
+        }
+    }
+
+    #[test]
+    fn block_is_xs4_33() {
+        assert_code_in_every_orientation(&[(0, 0), (1, 0), (0, 1), (1, 1)], "xs4_33");
+    }
+
+    #[test]
+    fn glider_is_xq4_153() {
+        assert_code_in_every_orientation(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)], "xq4_153");
+    }
+}
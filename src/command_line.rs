@@ -0,0 +1,219 @@
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::coordinates::Coordinates;
+use crate::theme::THEMES;
+
+/// Commands of the `:` command line
+pub const COMMANDS: [&str; 8] = [
+    "goto", "load", "random", "rule", "save", "speed", "step", "theme",
+];
+
+/// A command typed at the `:` prompt
+pub enum LineCommand {
+    /// Rule of the current engine, in that engine's notation
+    Rule(String),
+    /// Move the cursor to a grid cell
+    Goto(Coordinates),
+    /// Write the live cells to an RLE file
+    Save(String),
+    /// Place an RLE file at the cursor
+    Load(String),
+    Step(u64),
+    /// Generations per second
+    Speed(u32),
+    /// Soup density; the current one when not given
+    Random(Option<f64>),
+    Theme(String),
+}
+
+fn usage(name: &str) -> String {
+    let usage = match name {
+        "goto" => "goto X Y",
+        "load" => "load FILE.rle",
+        "random" => "random [DENSITY]",
+        "rule" => "rule RULE",
+        "save" => "save FILE.rle",
+        "speed" => "speed GENS_PER_SECOND",
+        "step" => "step [N]",
+        _ => "theme dark|light",
+    };
+    format!("usage: :{usage}")
+}
+
+/// The command a name stands for: itself, or the only command it starts
+fn resolve(name: &str) -> Result<&'static str, String> {
+    if let Some(exact) = COMMANDS.iter().find(|c| **c == name) {
+        return Ok(exact);
+    }
+    let matches: Vec<&str> = COMMANDS
+        .iter()
+        .copied()
+        .filter(|c| c.starts_with(name))
+        .collect();
+    match matches.as_slice() {
+        [only] => Ok(only),
+        [] => Err(format!("unknown command ':{name}'")),
+        several => Err(format!(
+            "ambiguous command ':{name}': {}",
+            several.join(", ")
+        )),
+    }
+}
+
+fn split_command(line: &str) -> (&str, &str) {
+    let line = line.trim_start();
+    match line.split_once(char::is_whitespace) {
+        Some((name, rest)) => (name, rest.trim()),
+        None => (line, ""),
+    }
+}
+
+impl FromStr for LineCommand {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let (name, rest) = split_command(line);
+        if name.is_empty() {
+            return Err(String::from("empty command"));
+        }
+        let name = resolve(name)?;
+        let args: Vec<&str> = rest.split_whitespace().collect();
+
+        let command = match (name, args.as_slice()) {
+            ("rule", [rule]) => LineCommand::Rule(rule.to_string()),
+            ("goto", [x, y]) => match (x.parse(), y.parse()) {
+                (Ok(x), Ok(y)) => LineCommand::Goto(Coordinates { x, y }),
+                _ => return Err(usage(name)),
+            },
+            ("save", [_, ..]) => LineCommand::Save(rest.to_string()),
+            ("load", [_, ..]) => LineCommand::Load(rest.to_string()),
+            ("step", []) => LineCommand::Step(1),
+            ("step", [n]) => LineCommand::Step(n.parse().map_err(|_| usage(name))?),
+            ("speed", [n]) => match n.parse() {
+                Ok(speed) if speed > 0 => LineCommand::Speed(speed),
+                _ => return Err(usage(name)),
+            },
+            ("random", []) => LineCommand::Random(None),
+            ("random", [density]) => match density.parse::<f64>() {
+                Ok(density) if (0.0..=1.0).contains(&density) => LineCommand::Random(Some(density)),
+                _ => return Err(String::from("density must be between 0 and 1")),
+            },
+            ("theme", [theme]) if THEMES.contains(theme) => LineCommand::Theme(theme.to_string()),
+            _ => return Err(usage(name)),
+        };
+        Ok(command)
+    }
+}
+
+/// Ways to complete the last word of a partly typed command line, each as
+/// the whole line once completed
+pub fn completions(line: &str) -> Vec<String> {
+    let (name, rest) = split_command(line);
+    if !line.trim_start().contains(char::is_whitespace) {
+        return COMMANDS
+            .iter()
+            .filter(|c| c.starts_with(name))
+            .map(|c| format!("{c} "))
+            .collect();
+    }
+
+    let Ok(name) = resolve(name) else {
+        return Vec::new();
+    };
+    match name {
+        "theme" => THEMES
+            .iter()
+            .filter(|t| t.starts_with(rest))
+            .map(|t| format!("{name} {t}"))
+            .collect(),
+        "load" | "save" => path_completions(rest)
+            .into_iter()
+            .map(|path| format!("{name} {path}"))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Directories and `.rle` files whose path starts with `partial`
+fn path_completions(partial: &str) -> Vec<String> {
+    let (dir, prefix) = match partial.rfind('/') {
+        Some(slash) => (&partial[..=slash], &partial[slash + 1..]),
+        None => ("", partial),
+    };
+    let Ok(entries) = fs::read_dir(if dir.is_empty() { "." } else { dir }) else {
+        return Vec::new();
+    };
+
+    let mut paths: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            if entry.path().is_dir() {
+                Some(format!("{dir}{name}/"))
+            } else if Path::new(&name).extension().is_some_and(|ext| ext == "rle") {
+                Some(format!("{dir}{name}"))
+            } else {
+                None
+            }
+        })
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<LineCommand, String> {
+        line.parse()
+    }
+
+    #[test]
+    fn each_verb_parses() {
+        assert!(matches!(parse("rule B36/S23"), Ok(LineCommand::Rule(r)) if r == "B36/S23"));
+        assert!(matches!(
+            parse("goto 3 4"),
+            Ok(LineCommand::Goto(Coordinates { x: 3, y: 4 }))
+        ));
+        assert!(
+            matches!(parse("save my glider.rle"), Ok(LineCommand::Save(f)) if f == "my glider.rle")
+        );
+        assert!(matches!(parse("load gun.rle"), Ok(LineCommand::Load(f)) if f == "gun.rle"));
+        assert!(matches!(parse("step"), Ok(LineCommand::Step(1))));
+        assert!(matches!(parse("step 10"), Ok(LineCommand::Step(10))));
+        assert!(matches!(parse("speed 30"), Ok(LineCommand::Speed(30))));
+        assert!(matches!(parse("random"), Ok(LineCommand::Random(None))));
+        assert!(matches!(parse("random 0.25"), Ok(LineCommand::Random(Some(d))) if d == 0.25));
+        assert!(matches!(parse("theme light"), Ok(LineCommand::Theme(t)) if t == "light"));
+        // Unique prefixes stand for the whole name
+        assert!(matches!(parse("  sp 5"), Ok(LineCommand::Speed(5))));
+        assert!(matches!(parse("g 0 0"), Ok(LineCommand::Goto(_))));
+    }
+
+    #[test]
+    fn bad_arguments_show_the_usage() {
+        for (line, error) in [
+            ("rule", "usage: :rule RULE"),
+            ("goto 3", "usage: :goto X Y"),
+            ("goto a b", "usage: :goto X Y"),
+            ("save", "usage: :save FILE.rle"),
+            ("load", "usage: :load FILE.rle"),
+            ("step -1", "usage: :step [N]"),
+            ("speed 0", "usage: :speed GENS_PER_SECOND"),
+            ("random 0.5 0.5", "usage: :random [DENSITY]"),
+            ("random 2", "density must be between 0 and 1"),
+            ("theme blue", "usage: :theme dark|light"),
+            ("", "empty command"),
+            ("frobnicate", "unknown command ':frobnicate'"),
+            ("s 1", "ambiguous command ':s': save, speed, step"),
+        ] {
+            assert_eq!(parse(line).err().as_deref(), Some(error), "{line:?}");
+        }
+    }
+}
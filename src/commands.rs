@@ -1,15 +1,19 @@
 use crossterm::event::Event;
 use crossterm::event::KeyCode;
-use crossterm::event::KeyEvent;
 use crossterm::event::KeyEventKind;
 use crossterm::event::KeyModifiers;
 use crossterm::event::MouseButton;
 use crossterm::event::MouseEvent;
 use crossterm::event::MouseEventKind;
 
-use crate::app::AppMode;
+use serde::Deserialize;
 
-#[derive(Debug, Clone)]
+use crate::app::{AppMode, PromptAction};
+use crate::keymap::{KeyChord, Keymap, Lookup};
+
+/// Everything the run loop can be asked to do. Keybinding files name the
+/// variants, e.g. `"ClearGrid"` or `{"PlacePattern": 0}`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub enum Command {
     Quit,
     Resize,
@@ -21,6 +25,14 @@ pub enum Command {
     MoveCursorRightBy(usize),
     MoveCursorToStartOfLine,
     MoveCursorToEndOfLine,
+    /// Top or bottom edge of the grid, keeping the column
+    MoveCursorToTop,
+    MoveCursorToBottom,
+    MoveCursorToRow(usize),
+    /// Top-left cell of the next or previous island of live cells, in
+    /// reading order from the cursor
+    NextCluster,
+    PreviousCluster,
     ToggleCellAlive,
     ToggleCellDead,
     ClearGrid,
@@ -28,13 +40,55 @@ pub enum Command {
     CyclePatternType,
     RotateLastPattern,
     ToggleSimulation,
+    TogglePauseWhenStable,
     StepSimulation,
+    StepBack,
+    CycleEngine,
+    NextRule,
+    PreviousRule,
+    SeedSingle,
+    SeedRandom,
+    RegenerateSoup,
+    CycleSymmetry,
+    ToggleSelection,
     SpeedUp,
     SpeedDown,
+    CancelJump,
+    MoreGensPerFrame,
+    FewerGensPerFrame,
+    NoiseUp,
+    NoiseDown,
     PlacePattern(usize),
     ShowHelp,
     ExitHelp,
     SetCursorPosition(usize, usize),
+    ToggleCensus,
+    ToggleLabels,
+    CycleDisplayMode,
+    ToggleChart,
+    ExportHistory,
+    /// Run a command several times, as typed with a count prefix
+    Repeat(usize, Box<Command>),
+    /// Run the last edit again, at the cursor
+    RepeatLastEdit,
+    // Breakpoint commands
+    EnterBreakpointsMode,
+    ExitBreakpointsMode,
+    BreakpointUp,
+    BreakpointDown,
+    ToggleBreakpoint,
+    RemoveBreakpoint,
+    AddRegionBreakpoint,
+    AddPeriodicBreakpoint,
+    // Prompt commands
+    OpenPrompt(PromptAction),
+    PromptInput(char),
+    PromptBackspace,
+    PromptSubmit,
+    PromptCancel,
+    PromptComplete,
+    PromptHistoryPrevious,
+    PromptHistoryNext,
     // Gallery commands
     EnterGalleryMode,
     ExitGalleryMode,
@@ -46,63 +100,277 @@ pub enum Command {
     NoOp,
 }
 
-pub struct CommandHandler;
+impl Command {
+    /// What the command does, as listed in the help popup
+    pub fn description(&self) -> &'static str {
+        match self {
+            Command::Quit => "quit",
+            Command::MoveCursorLeft => "move the cursor left",
+            Command::MoveCursorRight => "move the cursor right",
+            Command::MoveCursorUp => "move the cursor up",
+            Command::MoveCursorDown => "move the cursor down",
+            Command::MoveCursorLeftBy(_) => "move the cursor several cells left",
+            Command::MoveCursorRightBy(_) => "move the cursor several cells right",
+            Command::MoveCursorToStartOfLine => "move cursor to the beginning of the current line",
+            Command::MoveCursorToEndOfLine => "move cursor to the end of the current line",
+            Command::MoveCursorToTop => "move the cursor to the top edge of the grid",
+            Command::MoveCursorToBottom => "move the cursor to the bottom edge of the grid",
+            Command::MoveCursorToRow(_) => "move the cursor to a row of the grid",
+            Command::NextCluster => "jump to the next cluster of live cells",
+            Command::PreviousCluster => "jump back to the previous cluster of live cells",
+            Command::ToggleCellAlive => "toggle cursor point alive",
+            Command::ToggleCellDead => "toggle cursor point dead",
+            Command::ClearGrid => "clear the screen",
+            Command::PlaceLastPattern => "print the previous pattern again",
+            Command::CyclePatternType => {
+                "cycle through the pattern classes defined in patterns.json"
+            }
+            Command::RotateLastPattern => "rotate the current shape 90 degrees",
+            Command::ToggleSimulation => "toggle the simulation run loop",
+            Command::TogglePauseWhenStable => {
+                "toggle auto-pause when the universe becomes periodic"
+            }
+            Command::StepSimulation => "step the simulation forward",
+            Command::StepBack => "margolus: step backwards (reversible rules only)",
+            Command::CycleEngine => {
+                "cycle the simulation engine (life, elementary, turmite, margolus,\nlenia)"
+            }
+            Command::NextRule => "next rule (elementary number, turmite/margolus preset)",
+            Command::PreviousRule => "previous rule (elementary number, turmite/margolus preset)",
+            Command::SeedSingle => {
+                "elementary: seed a single cell in the top row at the cursor\n\
+                 turmite: place an ant at the cursor (again to turn it)\n\
+                 lenia: seed a random blob at the cursor"
+            }
+            Command::SeedRandom => {
+                "life: fill the selection (or a square at the cursor) with a new\n\
+                 random soup; elementary: seed a random top row; lenia: scatter\n\
+                 random blobs"
+            }
+            Command::RegenerateSoup => "life: place the last soup again from its recorded seed",
+            Command::CycleSymmetry => "cycle the soup symmetry (C1, C2_1 ... D8_4)",
+            Command::ToggleSelection => {
+                "mark a selection corner at the cursor, or clear the selection"
+            }
+            Command::SpeedUp => "speed up the simulation",
+            Command::SpeedDown => "slow down the simulation",
+            Command::CancelJump => "cancel a jump in progress",
+            Command::MoreGensPerFrame => "double the generations computed per frame",
+            Command::FewerGensPerFrame => "halve the generations computed per frame",
+            Command::NoiseUp => "raise the per-generation noise rate (life)",
+            Command::NoiseDown => "lower the per-generation noise rate (life)",
+            Command::PlacePattern(_) => "place pattern N of the current class",
+            Command::ShowHelp => "display help",
+            Command::ExitHelp => "exit help",
+            Command::ToggleCensus => {
+                "toggle the object census panel (replaces the pattern gallery)"
+            }
+            Command::ToggleLabels => "toggle labels and boxes around recognised gallery objects",
+            Command::CycleDisplayMode => {
+                "cycle the life display mode (cells, age, trails, heatmap, births\n\
+                 and deaths of the last generation)"
+            }
+            Command::ToggleChart => "toggle the population chart beneath the canvas",
+            Command::ExportHistory => "export the population history to population.csv",
+            Command::Repeat(_, command) => command.description(),
+            Command::RepeatLastEdit => "repeat the last edit at the cursor",
+            Command::EnterBreakpointsMode => "open the breakpoints panel",
+            Command::ExitBreakpointsMode => "exit breakpoints mode",
+            Command::BreakpointUp => "select the previous breakpoint",
+            Command::BreakpointDown => "select the next breakpoint",
+            Command::ToggleBreakpoint => "enable / disable the selected breakpoint",
+            Command::RemoveBreakpoint => "delete the selected breakpoint",
+            Command::AddRegionBreakpoint => "break when any cell in the selection (v) changes",
+            Command::AddPeriodicBreakpoint => "break when the universe becomes periodic",
+            Command::OpenPrompt(PromptAction::PopulationAbove) => {
+                "break when the population rises above N"
+            }
+            Command::OpenPrompt(PromptAction::PopulationBelow) => {
+                "break when the population falls below N"
+            }
+            Command::OpenPrompt(PromptAction::GenerationReached) => {
+                "break when generation N is reached"
+            }
+            Command::OpenPrompt(PromptAction::JumpToGeneration) => {
+                "jump to a generation (or +N ahead) without drawing; esc cancels"
+            }
+            Command::OpenPrompt(PromptAction::Command) => {
+                "command line: rule, goto, save, load, step, speed, random, theme\n\
+                 (tab completes, up/down recall earlier commands)"
+            }
+            Command::OpenPrompt(PromptAction::GallerySearch) => {
+                "search pattern and type names (enter selects the best match)"
+            }
+            Command::OpenPrompt(PromptAction::ConfirmClear) => "clear the screen, after confirming",
+            Command::EnterGalleryMode => "enter pattern gallery mode",
+            Command::ExitGalleryMode => "exit gallery mode",
+            Command::GalleryUp => "move up in the pattern tree",
+            Command::GalleryDown => "move down in the pattern tree",
+            Command::GalleryExpand => "expand type or enter children",
+            Command::GalleryCollapse => "collapse type or go to parent",
+            Command::GallerySelect => "select pattern (sets as last pattern)",
+            Command::Resize
+            | Command::SetCursorPosition(_, _)
+            | Command::PromptInput(_)
+            | Command::PromptBackspace
+            | Command::PromptSubmit
+            | Command::PromptCancel
+            | Command::PromptComplete
+            | Command::PromptHistoryPrevious
+            | Command::PromptHistoryNext
+            | Command::NoOp => "",
+        }
+    }
 
-impl CommandHandler {
-    /// Convert a crossterm event into a command based on current mode
-    pub fn event_to_command(event: &Event, mode: AppMode) -> Command {
-        match mode {
-            AppMode::Help => Self::help_event_to_command(event),
-            AppMode::PatternGallery => Self::gallery_event_to_command(event),
-            AppMode::Normal => Self::normal_event_to_command(event),
+    /// Whether the command changes the grid at the cursor, so `.` can
+    /// repeat it
+    fn is_edit(&self) -> bool {
+        match self {
+            Command::ToggleCellAlive
+            | Command::ToggleCellDead
+            | Command::PlaceLastPattern
+            | Command::PlacePattern(_)
+            | Command::SeedSingle
+            | Command::SeedRandom
+            | Command::RegenerateSoup => true,
+            Command::Repeat(_, command) => command.is_edit(),
+            _ => false,
         }
     }
 
-    fn help_event_to_command(event: &Event) -> Command {
-        match event {
-            Event::Key(key) => {
-                if (key.code == KeyCode::Esc || key.code == KeyCode::Char('h'))
-                    && key.kind == KeyEventKind::Press
-                {
-                    return Command::ExitHelp;
-                }
+    /// Apply a count prefix. Commands that take a number use it directly
+    /// (`10 l` moves ten cells, `3 P` places the third pattern, `5 G` goes
+    /// to row 5); the rest run that many times.
+    fn with_count(self, count: Option<usize>) -> Command {
+        let Some(count) = count else {
+            return self;
+        };
+        match self {
+            Command::MoveCursorLeft => Command::MoveCursorLeftBy(count),
+            Command::MoveCursorRight => Command::MoveCursorRightBy(count),
+            Command::MoveCursorToTop | Command::MoveCursorToBottom => {
+                Command::MoveCursorToRow(count - 1)
             }
-            Event::Resize(_, _) => return Command::Resize,
-            _ => {}
+            Command::PlacePattern(_) => Command::PlacePattern(count - 1),
+            command => Command::Repeat(count, Box::new(command)),
         }
-        Command::NoOp
     }
+}
 
-    fn gallery_event_to_command(event: &Event) -> Command {
-        match event {
-            Event::Key(key) => {
-                if key.kind != KeyEventKind::Press {
+/// Largest count prefix, so a slip of the finger cannot queue millions of
+/// commands
+const MAX_COUNT: usize = 99_999;
+
+/// Turns events into commands, collecting key sequences (`g g`), count
+/// prefixes (`10 l`) and the last edit for `.`
+#[derive(Default)]
+pub struct CommandHandler {
+    /// Keys typed so far of an unfinished sequence
+    pending: Vec<KeyChord>,
+    count: Option<usize>,
+    /// The last edit and its count
+    last_edit: Option<(Command, Option<usize>)>,
+}
+
+impl CommandHandler {
+    /// Convert a crossterm event into a command based on current mode and
+    /// the active key bindings
+    pub fn event_to_command(&mut self, event: &Event, mode: AppMode, keymap: &Keymap) -> Command {
+        match (mode, event) {
+            (AppMode::Prompt, _) => {
+                self.reset();
+                Self::prompt_event_to_command(event)
+            }
+            (_, Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                self.key_to_command(KeyChord::from_event(key), mode, keymap)
+            }
+            (AppMode::Normal, Event::Mouse(mouse)) => Self::mouse_to_command(mouse),
+            (_, Event::Resize(_, _)) => Command::Resize,
+            _ => Command::NoOp,
+        }
+    }
+
+    /// The count and keys of an unfinished command, e.g. `12 g`
+    pub fn pending_keys(&self) -> String {
+        let keys = self.pending.iter().map(KeyChord::to_string);
+        self.count
+            .map(|count| count.to_string())
+            .into_iter()
+            .chain(keys)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn reset(&mut self) {
+        self.pending.clear();
+        self.count = None;
+    }
+
+    fn key_to_command(&mut self, chord: KeyChord, mode: AppMode, keymap: &Keymap) -> Command {
+        if mode == AppMode::Normal && keymap.has_counts() && self.pending.is_empty() {
+            // A leading 0 is a key of its own (start of line)
+            match (chord.digit(), self.count) {
+                (Some(digit), Some(count)) => {
+                    self.count = Some((count * 10 + digit).min(MAX_COUNT));
+                    return Command::NoOp;
+                }
+                (Some(digit), None) if digit > 0 => {
+                    self.count = Some(digit);
                     return Command::NoOp;
                 }
-                return match key.code {
-                    KeyCode::Char('g') | KeyCode::Esc => Command::ExitGalleryMode,
-                    KeyCode::Up => Command::GalleryUp,
-                    KeyCode::Down => Command::GalleryDown,
-                    KeyCode::Left => Command::GalleryCollapse,
-                    KeyCode::Right => Command::GalleryExpand,
-                    KeyCode::Enter => Command::GallerySelect,
-                    KeyCode::Char('q') => Command::Quit,
-                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        Command::Quit
-                    }
-                    _ => Command::NoOp,
-                };
-            }
-            Event::Resize(_, _) => return Command::Resize,
-            _ => {}
+                _ => {}
+            }
+        }
+        if chord == KeyChord::new(KeyCode::Esc) && !self.pending_keys().is_empty() {
+            self.reset();
+            return Command::NoOp;
+        }
+
+        self.pending.push(chord);
+        match keymap.lookup(mode, &self.pending) {
+            Lookup::Command(command) => {
+                let count = self.count.take();
+                self.pending.clear();
+                self.resolve(command, count)
+            }
+            Lookup::Prefix => Command::NoOp,
+            Lookup::Unbound => {
+                self.reset();
+                Command::NoOp
+            }
         }
-        Command::NoOp
     }
 
-    fn normal_event_to_command(event: &Event) -> Command {
+    /// Apply the count, remembering edits and replaying the last one for `.`
+    fn resolve(&mut self, command: Command, count: Option<usize>) -> Command {
+        match command {
+            Command::RepeatLastEdit => match &self.last_edit {
+                Some((edit, last_count)) => edit.clone().with_count(count.or(*last_count)),
+                None => Command::NoOp,
+            },
+            command if command.is_edit() => {
+                self.last_edit = Some((command.clone(), count));
+                command.with_count(count)
+            }
+            command => command.with_count(count),
+        }
+    }
+
+    fn prompt_event_to_command(event: &Event) -> Command {
         match event {
-            Event::Key(key) => Self::key_to_command(key),
-            Event::Mouse(mouse) => Self::mouse_to_command(mouse),
+            Event::Key(key) if key.kind == KeyEventKind::Press => match key.code {
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    Command::Quit
+                }
+                KeyCode::Char(c) => Command::PromptInput(c),
+                KeyCode::Backspace => Command::PromptBackspace,
+                KeyCode::Enter => Command::PromptSubmit,
+                KeyCode::Esc => Command::PromptCancel,
+                KeyCode::Tab => Command::PromptComplete,
+                KeyCode::Up => Command::PromptHistoryPrevious,
+                KeyCode::Down => Command::PromptHistoryNext,
+                _ => Command::NoOp,
+            },
             Event::Resize(_, _) => Command::Resize,
             _ => Command::NoOp,
         }
@@ -116,50 +384,4 @@ impl CommandHandler {
             _ => Command::NoOp,
         }
     }
-
-    fn key_to_command(key: &KeyEvent) -> Command {
-        if key.kind != KeyEventKind::Press {
-            return Command::NoOp;
-        }
-
-        match key.code {
-            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Command::Quit,
-            KeyCode::Char('q') => Command::Quit,
-            KeyCode::Left => Command::MoveCursorLeft,
-            KeyCode::Right => Command::MoveCursorRight,
-            KeyCode::Up => Command::MoveCursorUp,
-            KeyCode::Down => Command::MoveCursorDown,
-            KeyCode::Backspace => Command::ToggleCellDead,
-            KeyCode::BackTab => Command::MoveCursorLeftBy(4),
-            KeyCode::Tab => Command::MoveCursorRightBy(4),
-            KeyCode::Char('a') => Command::ToggleCellAlive,
-            KeyCode::Char('b') => Command::MoveCursorToStartOfLine,
-            KeyCode::Char('c') => Command::ClearGrid,
-            KeyCode::Char('d') => Command::ToggleCellDead,
-            KeyCode::Char('e') => Command::MoveCursorToEndOfLine,
-            KeyCode::Char('g') => Command::EnterGalleryMode,
-            KeyCode::Char('h') => Command::ShowHelp,
-            KeyCode::Char('l') => Command::PlaceLastPattern,
-            KeyCode::Char('p') => Command::CyclePatternType,
-            KeyCode::Char('r') => Command::RotateLastPattern,
-            KeyCode::Char('s') => Command::ToggleSimulation,
-            KeyCode::Char(' ') => Command::StepSimulation,
-            KeyCode::Char('+') => Command::SpeedUp,
-            KeyCode::Char('-') => Command::SpeedDown,
-            KeyCode::Char(c) if c.is_ascii_digit() => {
-                if let Some(digit) = c.to_digit(10) {
-                    let mut index = digit as usize;
-                    if index > 0 {
-                        index -= 1;
-                        Command::PlacePattern(index)
-                    } else {
-                        Command::NoOp
-                    }
-                } else {
-                    Command::NoOp
-                }
-            }
-            _ => Command::NoOp,
-        }
-    }
 }
@@ -0,0 +1,167 @@
+use crate::grid::Grid;
+use crate::health::Health;
+use crate::health::Health::{Alive, Dead};
+use crate::rng::Rng;
+
+/// Wolfram elementary (1D, radius 1) cellular automaton.
+///
+/// Each generation is written to the row below the previous one inside a
+/// window of grid rows. Once the window is full the rows scroll up, so the
+/// newest generation is always the bottom row. Each row wraps around at the
+/// grid edges.
+#[derive(Clone)]
+pub struct Elementary {
+    pub rule: u8,
+    top: usize,
+    rows: usize,
+    row: usize,
+}
+
+impl Elementary {
+    pub fn new(rule: u8) -> Self {
+        Self {
+            rule,
+            top: 0,
+            rows: 0,
+            row: 0,
+        }
+    }
+
+    /// Set the band of grid rows used for the history, keeping the current
+    /// row inside it
+    pub fn set_window(&mut self, top: usize, rows: usize) {
+        if self.top != top || self.rows != rows {
+            self.top = top;
+            self.rows = rows;
+            self.row = self.row.clamp(top, (top + rows).saturating_sub(1).max(top));
+        }
+    }
+
+    /// Restart the history from the top row of the window
+    pub fn restart(&mut self) {
+        self.row = self.top;
+    }
+
+    pub fn next_rule(&mut self) {
+        self.rule = self.rule.wrapping_add(1);
+    }
+
+    pub fn previous_rule(&mut self) {
+        self.rule = self.rule.wrapping_sub(1);
+    }
+
+    pub fn step(&mut self, grid: &mut Grid) {
+        if self.rows == 0 {
+            return;
+        }
+
+        let current = match grid.line(self.row) {
+            Some(line) => self.next_line(line),
+            None => return,
+        };
+
+        let bottom = self.top + self.rows - 1;
+        if self.row >= bottom {
+            grid.scroll_up(self.top, self.rows);
+        } else {
+            self.row += 1;
+        }
+
+        grid.set_line(self.row, current);
+    }
+
+    /// The next generation of a row, which wraps around at its ends
+    fn next_line(&self, line: &[Health]) -> Vec<Health> {
+        let width = line.len();
+        let bit = |x: usize| matches!(line[x % width], Alive) as u8;
+
+        (0..width)
+            .map(|x| {
+                let neighborhood = (bit(x + width - 1) << 2) | (bit(x) << 1) | bit(x + 1);
+                if (self.rule >> neighborhood) & 1 == 1 {
+                    Alive
+                } else {
+                    Dead
+                }
+            })
+            .collect()
+    }
+
+    /// Clear the window and seed a single live cell in the top row
+    pub fn seed_single(&mut self, grid: &mut Grid, x: usize) {
+        self.clear_window(grid);
+        let mut line = vec![Dead; grid.get_size().width];
+        if x < line.len() {
+            line[x] = Alive;
+        }
+        grid.set_line(self.top, line);
+    }
+
+    /// Clear the window and fill the top row with random cells
+    pub fn seed_random(&mut self, grid: &mut Grid, rng: &mut Rng) {
+        self.clear_window(grid);
+        let line = (0..grid.get_size().width)
+            .map(|_| if rng.chance(0.5) { Alive } else { Dead })
+            .collect();
+        grid.set_line(self.top, line);
+    }
+
+    fn clear_window(&mut self, grid: &mut Grid) {
+        let width = grid.get_size().width;
+        for y in self.top..self.top + self.rows {
+            grid.set_line(y, vec![Dead; width]);
+        }
+        self.restart();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::size::Size;
+
+    /// Run a rule from a single live cell and return the live columns of
+    /// each row of the history
+    fn history(rule: u8, width: usize, seed: usize, generations: usize) -> Vec<Vec<usize>> {
+        let mut grid = Grid::new(Size {
+            width,
+            height: generations + 1,
+        });
+        let mut elementary = Elementary::new(rule);
+        elementary.set_window(0, generations + 1);
+        elementary.seed_single(&mut grid, seed);
+        for _ in 0..generations {
+            elementary.step(&mut grid);
+        }
+
+        (0..=generations)
+            .map(|y| {
+                let line = grid.line(y).unwrap();
+                (0..width).filter(|x| matches!(line[*x], Alive)).collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rule_90_draws_the_sierpinski_triangle() {
+        let rows = history(90, 16, 7, 7);
+        // Cells of Pascal's triangle with odd entries
+        let expected: Vec<Vec<usize>> = vec![
+            vec![7],
+            vec![6, 8],
+            vec![5, 9],
+            vec![4, 6, 8, 10],
+            vec![3, 11],
+            vec![2, 4, 10, 12],
+            vec![1, 5, 9, 13],
+            vec![0, 2, 4, 6, 8, 10, 12, 14],
+        ];
+        assert_eq!(rows, expected);
+    }
+
+    #[test]
+    fn rule_30_wraps_at_the_grid_edges() {
+        let rows = history(30, 8, 0, 2);
+        assert_eq!(rows, vec![vec![0], vec![0, 1, 7], vec![2, 6, 7]]);
+    }
+}
@@ -0,0 +1,82 @@
+/// A query matched against a name
+pub struct FuzzyMatch {
+    /// Higher is better: consecutive letters and word starts score more,
+    /// skipped letters less
+    pub score: i64,
+    /// Character positions in the name of the matched letters
+    pub positions: Vec<usize>,
+}
+
+/// Match the letters of `query` in order, ignoring case, anywhere in
+/// `name`. Each letter takes its earliest place after the previous one.
+pub fn fuzzy_match(query: &str, name: &str) -> Option<FuzzyMatch> {
+    let name: Vec<char> = name.chars().collect();
+    let mut positions = Vec::new();
+    let mut score = 0;
+    let mut start = 0;
+
+    for wanted in query.chars().filter(|c| !c.is_whitespace()) {
+        let offset = name[start..]
+            .iter()
+            .position(|c| c.to_lowercase().eq(wanted.to_lowercase()))?;
+        let index = start + offset;
+
+        score += 1;
+        if positions.last().is_some_and(|last| last + 1 == index) {
+            score += 5;
+        }
+        if index == 0 || !name[index - 1].is_alphanumeric() {
+            score += 3;
+        }
+        score -= offset as i64;
+
+        positions.push(index);
+        start = index + 1;
+    }
+
+    Some(FuzzyMatch { score, positions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letters_match_in_order_at_their_earliest_place() {
+        let found = fuzzy_match("gdr", "glider gun").unwrap();
+        assert_eq!(found.positions, vec![0, 3, 5]);
+        assert_eq!(
+            fuzzy_match("gg", "glider gun").unwrap().positions,
+            vec![0, 7]
+        );
+    }
+
+    #[test]
+    fn missing_or_out_of_order_letters_do_not_match() {
+        assert!(fuzzy_match("x", "glider").is_none());
+        assert!(fuzzy_match("rg", "glider").is_none());
+        assert!(fuzzy_match("glider!", "glider").is_none());
+    }
+
+    #[test]
+    fn case_is_ignored() {
+        assert_eq!(
+            fuzzy_match("GoSpEr", "gosper glider gun")
+                .unwrap()
+                .positions,
+            vec![0, 1, 2, 3, 4, 5]
+        );
+        assert_eq!(
+            fuzzy_match("lwss", "LWSS").unwrap().positions,
+            vec![0, 1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn prefixes_and_runs_beat_scattered_letters() {
+        let score = |query, name| fuzzy_match(query, name).unwrap().score;
+        assert!(score("pul", "pulsar") > score("pul", "spurrel"));
+        assert!(score("gun", "glider gun") > score("gun", "glider burn"));
+        assert!(score("acorn", "acorn") > score("acorn", "a corona"));
+    }
+}
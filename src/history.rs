@@ -0,0 +1,143 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+/// Population of the grid at every generation of the run
+#[derive(Clone, Default)]
+pub struct PopulationHistory {
+    /// Generation of the first recorded value
+    start: u64,
+    values: Vec<usize>,
+}
+
+impl PopulationHistory {
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Record the population at a generation. Going back in time (reverse
+    /// stepping) drops the later values; a gap restarts the series.
+    pub fn record(&mut self, generation: u64, population: usize) {
+        let end = self.start + self.values.len() as u64;
+        if self.values.is_empty() || generation < self.start || generation > end {
+            self.values.clear();
+            self.start = generation;
+        } else {
+            self.values.truncate((generation - self.start) as usize);
+        }
+        self.values.push(population);
+    }
+
+    /// (generation, population) of the lowest value, earliest first
+    pub fn min(&self) -> Option<(u64, usize)> {
+        self.points()
+            .min_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)))
+    }
+
+    /// (generation, population) of the highest value, earliest first
+    pub fn max(&self) -> Option<(u64, usize)> {
+        self.points()
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+    }
+
+    pub fn last(&self) -> Option<(u64, usize)> {
+        self.points().next_back()
+    }
+
+    /// Every (generation, population) pair in order
+    pub fn points(&self) -> impl DoubleEndedIterator<Item = (u64, usize)> + '_ {
+        let start = self.start;
+        self.values
+            .iter()
+            .enumerate()
+            .map(move |(i, value)| (start + i as u64, *value))
+    }
+
+    /// The (generation, population) pairs recorded after a generation, or
+    /// all of them
+    pub fn points_after(&self, generation: Option<u64>) -> Vec<(u64, usize)> {
+        let skip = match generation {
+            Some(generation) => (generation + 1).saturating_sub(self.start) as usize,
+            None => 0,
+        };
+        self.points().skip(skip).collect()
+    }
+
+    /// At most `limit` evenly spaced points, always including the first and
+    /// last ones
+    pub fn sample(&self, limit: usize) -> Vec<(u64, usize)> {
+        let len = self.values.len();
+        if len <= limit {
+            return self.points().collect();
+        }
+        if limit < 2 {
+            return self.last().into_iter().take(limit).collect();
+        }
+        (0..limit)
+            .map(|i| {
+                let index = i * (len - 1) / (limit - 1);
+                (self.start + index as u64, self.values[index])
+            })
+            .collect()
+    }
+
+    /// Write the series as `generation,population` rows, returning the row count
+    pub fn write_csv(&self, path: &str) -> io::Result<usize> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "generation,population")?;
+        for (generation, population) in self.points() {
+            writeln!(out, "{generation},{population}")?;
+        }
+        out.flush()?;
+        Ok(self.values.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(start: u64, len: usize) -> PopulationHistory {
+        let mut history = PopulationHistory::default();
+        for i in 0..len {
+            history.record(start + i as u64, i * 3);
+        }
+        history
+    }
+
+    #[test]
+    fn samples_keep_the_ends_within_the_limit() {
+        for len in [1, 2, 9, 10, 11, 100, 1001] {
+            let history = history(40, len);
+            let points: Vec<(u64, usize)> = history.points().collect();
+            for limit in [2, 3, 7, 10, 64] {
+                let sampled = history.sample(limit);
+                assert!(sampled.len() <= limit, "{} points, limit {}", len, limit);
+                assert_eq!(sampled.len(), len.min(limit), "{len} points, limit {limit}");
+                assert_eq!(sampled.first(), points.first());
+                assert_eq!(sampled.last(), points.last());
+                assert!(sampled.windows(2).all(|pair| pair[0].0 < pair[1].0));
+                assert!(sampled.iter().all(|point| points.contains(point)));
+            }
+            assert_eq!(history.sample(1), vec![*points.last().unwrap()]);
+            assert!(history.sample(0).is_empty());
+        }
+        assert!(PopulationHistory::default().sample(10).is_empty());
+    }
+
+    #[test]
+    fn csv_has_a_row_per_generation() {
+        let path =
+            std::env::temp_dir().join(format!("rustmaton-history-{}.csv", std::process::id()));
+        let path = path.to_str().unwrap();
+        let rows = history(5, 3).write_csv(path).unwrap();
+        let written = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(rows, 3);
+        assert_eq!(written, "generation,population\n5,0\n6,3\n7,6\n");
+    }
+}
@@ -0,0 +1,794 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+use crate::app::{AppMode, PromptAction};
+use crate::commands::Command;
+
+/// A key together with the ctrl/alt modifiers held with it, e.g. `ctrl+c`,
+/// `B` or `space`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn new(code: KeyCode) -> Self {
+        Self {
+            code,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    pub fn char(c: char) -> Self {
+        Self::new(KeyCode::Char(c))
+    }
+
+    pub fn ctrl(c: char) -> Self {
+        Self {
+            code: KeyCode::Char(c),
+            modifiers: KeyModifiers::CONTROL,
+        }
+    }
+
+    /// The chord of a key press. Shift is dropped because it is already
+    /// part of the key (`B`, `<`, backtab).
+    pub fn from_event(key: &KeyEvent) -> Self {
+        Self {
+            code: key.code,
+            modifiers: key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT),
+        }
+    }
+
+    /// The digit of an unmodified digit key
+    pub fn digit(&self) -> Option<usize> {
+        match self.code {
+            KeyCode::Char(c) if self.modifiers.is_empty() => {
+                c.to_digit(10).map(|digit| digit as usize)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Parse a space-separated key sequence such as `g g` or `ctrl+c`
+fn parse_keys(s: &str) -> Result<Vec<KeyChord>, String> {
+    let keys = s
+        .split_whitespace()
+        .map(str::parse)
+        .collect::<Result<Vec<KeyChord>, String>>()?;
+    if keys.is_empty() {
+        return Err(String::from("empty key"));
+    }
+    Ok(keys)
+}
+
+fn keys_to_string(keys: &[KeyChord]) -> String {
+    keys.iter()
+        .map(KeyChord::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Which set of default keys the bindings start from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    #[default]
+    Standard,
+    /// hjkl movement, count prefixes, `g g`/`G`, `w`/`b` and `.`
+    Vim,
+}
+
+impl FromStr for Profile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "standard" => Ok(Profile::Standard),
+            "vim" => Ok(Profile::Vim),
+            _ => Err(format!(
+                "unknown keybinding profile '{}', expected standard or vim",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Profile::Standard => write!(f, "standard"),
+            Profile::Vim => write!(f, "vim"),
+        }
+    }
+}
+
+const NAMED_KEYS: [(&str, KeyCode); 16] = [
+    ("space", KeyCode::Char(' ')),
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("tab", KeyCode::Tab),
+    ("backtab", KeyCode::BackTab),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+];
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    /// Parse `[ctrl+][alt+][shift+]KEY`, where KEY is a single character or
+    /// one of `space`, `enter`, `esc`, `tab`, `backtab`, `backspace`,
+    /// `delete`, `insert`, `home`, `end`, `pageup`, `pagedown`, the arrows
+    /// `up`/`down`/`left`/`right`, or `f1`-`f12`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // A trailing `+` is the plus key itself: `+`, `ctrl++`
+        let (prefix, key) = if s == "+" {
+            ("", "+")
+        } else if let Some(prefix) = s.strip_suffix("++") {
+            (prefix, "+")
+        } else {
+            match s.rsplit_once('+') {
+                Some((prefix, key)) => (prefix, key),
+                None => ("", s),
+            }
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        let mut shift = false;
+        for modifier in prefix.split('+').filter(|m| !m.is_empty()) {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => modifiers |= KeyModifiers::CONTROL,
+                "alt" => modifiers |= KeyModifiers::ALT,
+                "shift" => shift = true,
+                _ => return Err(format!("unknown modifier '{}' in '{}'", modifier, s)),
+            }
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => {
+                let name = key.to_ascii_lowercase();
+                let function = name
+                    .strip_prefix('f')
+                    .and_then(|n| n.parse::<u8>().ok())
+                    .filter(|n| (1..=12).contains(n));
+                match (NAMED_KEYS.iter().find(|(n, _)| *n == name), function) {
+                    (Some((_, code)), _) => *code,
+                    (None, Some(n)) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key '{}' in '{}'", key, s)),
+                }
+            }
+        };
+
+        let code = match (shift, code) {
+            (false, code) => code,
+            (true, KeyCode::Tab) => KeyCode::BackTab,
+            (true, KeyCode::Char(c)) if c.is_ascii_alphabetic() => {
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            (true, _) => {
+                return Err(format!(
+                    "shift only applies to letters and tab in '{}'; write the shifted character instead",
+                    s
+                ))
+            }
+        };
+        Ok(Self { code, modifiers })
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            code => match NAMED_KEYS.iter().find(|(_, c)| *c == code) {
+                Some((name, _)) => write!(f, "{}", name),
+                None => write!(f, "{:?}", code),
+            },
+        }
+    }
+}
+
+/// A key sequence, usually a single chord, and the command it runs
+#[derive(Clone, Debug)]
+pub struct Binding {
+    pub keys: Vec<KeyChord>,
+    pub command: Command,
+}
+
+fn bind(chord: KeyChord, command: Command) -> Binding {
+    Binding {
+        keys: vec![chord],
+        command,
+    }
+}
+
+fn bind_sequence(keys: &[KeyChord], command: Command) -> Binding {
+    Binding {
+        keys: keys.to_vec(),
+        command,
+    }
+}
+
+/// What a key sequence typed so far amounts to
+pub enum Lookup {
+    Command(Command),
+    /// The start of a longer binding; wait for the next key
+    Prefix,
+    Unbound,
+}
+
+/// Key bindings of every mode driven by key presses. Prompt mode takes
+/// typed text and is not configurable.
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    profile: Profile,
+    normal: Vec<Binding>,
+    help: Vec<Binding>,
+    gallery: Vec<Binding>,
+    breakpoints: Vec<Binding>,
+}
+
+/// A keybindings file: the profile to start from and, for each mode, key
+/// sequences mapped to `Command` variant names, e.g.
+/// `{"profile": "vim", "normal": {"h": "MoveCursorLeft", "?": "ShowHelp"}}`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeymapFile {
+    #[serde(default)]
+    profile: Option<Profile>,
+    #[serde(default)]
+    normal: BTreeMap<String, Command>,
+    #[serde(default)]
+    help: BTreeMap<String, Command>,
+    #[serde(default)]
+    gallery: BTreeMap<String, Command>,
+    #[serde(default)]
+    breakpoints: BTreeMap<String, Command>,
+}
+
+impl Keymap {
+    /// The default bindings of a profile
+    pub fn new(profile: Profile) -> Self {
+        match profile {
+            Profile::Standard => Self::standard(),
+            Profile::Vim => Self::vim(),
+        }
+    }
+
+    /// Parse a keybindings file and apply it over the default bindings of
+    /// its profile, or of `profile` when given. Binding keys to `NoOp`
+    /// unbinds them.
+    pub fn from_json(json: &str, profile: Option<Profile>) -> Result<Self, String> {
+        let file: KeymapFile = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        let mut keymap = Keymap::new(profile.or(file.profile).unwrap_or_default());
+        for (mode, entries) in [
+            (AppMode::Normal, file.normal),
+            (AppMode::Help, file.help),
+            (AppMode::PatternGallery, file.gallery),
+            (AppMode::Breakpoints, file.breakpoints),
+        ] {
+            keymap.apply(mode, entries)?;
+        }
+        keymap.validate()?;
+        Ok(keymap)
+    }
+
+    fn apply(&mut self, mode: AppMode, entries: BTreeMap<String, Command>) -> Result<(), String> {
+        let mut seen: Vec<Vec<KeyChord>> = Vec::new();
+        for (key, command) in entries {
+            let keys = parse_keys(&key).map_err(|e| format!("{} mode: {}", mode_name(mode), e))?;
+            if seen.contains(&keys) {
+                return Err(format!(
+                    "{} mode: '{}' is bound more than once",
+                    mode_name(mode),
+                    keys_to_string(&keys)
+                ));
+            }
+            seen.push(keys.clone());
+            if !is_bindable(mode, &command) {
+                return Err(format!(
+                    "{} mode: {:?} cannot be bound in this mode",
+                    mode_name(mode),
+                    command
+                ));
+            }
+
+            let bindings = self.bindings_mut(mode);
+            match bindings.iter().position(|b| b.keys == keys) {
+                Some(index) if matches!(command, Command::NoOp) => {
+                    bindings.remove(index);
+                }
+                Some(index) => bindings[index].command = command,
+                None if matches!(command, Command::NoOp) => {}
+                None => bindings.push(Binding { keys, command }),
+            }
+        }
+        Ok(())
+    }
+
+    /// Every mode must keep a way out, and every binding must be reachable
+    fn validate(&self) -> Result<(), String> {
+        for mode in [
+            AppMode::Normal,
+            AppMode::Help,
+            AppMode::PatternGallery,
+            AppMode::Breakpoints,
+        ] {
+            let bindings = self.bindings(mode);
+            for binding in bindings {
+                if let Some(shorter) = bindings.iter().find(|b| {
+                    b.keys.len() < binding.keys.len() && binding.keys.starts_with(&b.keys)
+                }) {
+                    return Err(format!(
+                        "{} mode: '{}' can never be typed because '{}' is bound",
+                        mode_name(mode),
+                        keys_to_string(&binding.keys),
+                        keys_to_string(&shorter.keys)
+                    ));
+                }
+                let counted = binding.keys[0].digit().filter(|digit| *digit > 0);
+                if mode == AppMode::Normal && self.has_counts() && counted.is_some() {
+                    return Err(format!(
+                        "normal mode: '{}' can never be typed because digits are counts in the {} profile",
+                        keys_to_string(&binding.keys),
+                        self.profile
+                    ));
+                }
+            }
+        }
+
+        for (mode, required) in [
+            (AppMode::Normal, Command::Quit),
+            (AppMode::Help, Command::ExitHelp),
+            (AppMode::PatternGallery, Command::ExitGalleryMode),
+            (AppMode::Breakpoints, Command::ExitBreakpointsMode),
+        ] {
+            if self.keys_for(mode, &required).is_empty() {
+                return Err(format!(
+                    "{} mode: no key is bound to {:?}",
+                    mode_name(mode),
+                    required
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether a number typed before a normal-mode command repeats it
+    pub fn has_counts(&self) -> bool {
+        self.profile == Profile::Vim
+    }
+
+    pub fn bindings(&self, mode: AppMode) -> &[Binding] {
+        match mode {
+            AppMode::Normal => &self.normal,
+            AppMode::Help => &self.help,
+            AppMode::PatternGallery => &self.gallery,
+            AppMode::Breakpoints => &self.breakpoints,
+            AppMode::Prompt => &[],
+        }
+    }
+
+    fn bindings_mut(&mut self, mode: AppMode) -> &mut Vec<Binding> {
+        match mode {
+            AppMode::Normal | AppMode::Prompt => &mut self.normal,
+            AppMode::Help => &mut self.help,
+            AppMode::PatternGallery => &mut self.gallery,
+            AppMode::Breakpoints => &mut self.breakpoints,
+        }
+    }
+
+    /// The command bound to the keys typed so far in a mode
+    pub fn lookup(&self, mode: AppMode, keys: &[KeyChord]) -> Lookup {
+        let bindings = self.bindings(mode);
+        if let Some(binding) = bindings.iter().find(|b| b.keys == keys) {
+            Lookup::Command(binding.command.clone())
+        } else if bindings.iter().any(|b| b.keys.starts_with(keys)) {
+            Lookup::Prefix
+        } else {
+            Lookup::Unbound
+        }
+    }
+
+    pub fn keys_for(&self, mode: AppMode, command: &Command) -> Vec<String> {
+        self.bindings(mode)
+            .iter()
+            .filter(|b| b.command == *command)
+            .map(|b| keys_to_string(&b.keys))
+            .collect()
+    }
+
+    /// Help text listing the active bindings of every mode
+    pub fn help_text(&self) -> String {
+        let mut text = String::new();
+        if self.has_counts() {
+            text.push_str(&format!(
+                "\n# {} keys: a number before a command repeats it, e.g. 10 l or\n# 5 space; 'g g' and 'G' go to that row and 'P' places that pattern\n",
+                self.profile
+            ));
+        }
+        self.write_section(&mut text, AppMode::Normal, "command keys", None);
+        self.write_section(&mut text, AppMode::Help, "help", None);
+        self.write_section(
+            &mut text,
+            AppMode::PatternGallery,
+            "gallery mode",
+            Some(Command::EnterGalleryMode),
+        );
+        self.write_section(
+            &mut text,
+            AppMode::Breakpoints,
+            "breakpoints mode",
+            Some(Command::EnterBreakpointsMode),
+        );
+        text
+    }
+
+    /// One line per description, listing every key bound to it, in the
+    /// order the keys were bound
+    fn write_section(&self, text: &mut String, mode: AppMode, title: &str, enter: Option<Command>) {
+        let enter_keys = enter
+            .map(|command| self.keys_for(AppMode::Normal, &command))
+            .unwrap_or_default();
+        match enter_keys.first() {
+            Some(key) => text.push_str(&format!("\n# {} (press '{}' to enter):\n", title, key)),
+            None => text.push_str(&format!("\n# {}:\n", title)),
+        }
+
+        let mut lines: Vec<(&str, Vec<String>)> = Vec::new();
+        for binding in self.bindings(mode) {
+            let description = binding.command.description();
+            match lines.iter_mut().find(|(d, _)| *d == description) {
+                Some((_, keys)) => keys.push(keys_to_string(&binding.keys)),
+                None => lines.push((description, vec![keys_to_string(&binding.keys)])),
+            }
+        }
+        for (description, keys) in lines {
+            let keys = keys.join(" / ");
+            let mut description = description.lines();
+            let first = description.next().unwrap_or_default();
+            if keys.chars().count() < 8 {
+                text.push_str(&format!("{:<8}- {}\n", keys, first));
+            } else {
+                text.push_str(&format!("{} - {}\n", keys, first));
+            }
+            for line in description {
+                text.push_str(&format!("{:10}{}\n", "", line));
+            }
+        }
+    }
+}
+
+fn mode_name(mode: AppMode) -> &'static str {
+    match mode {
+        AppMode::Normal => "normal",
+        AppMode::Help => "help",
+        AppMode::PatternGallery => "gallery",
+        AppMode::Breakpoints => "breakpoints",
+        AppMode::Prompt => "prompt",
+    }
+}
+
+/// Whether a command makes sense bound to a key in a mode. Commands the
+/// run loop generates itself (resize, mouse, prompt typing) never do.
+fn is_bindable(mode: AppMode, command: &Command) -> bool {
+    use Command::*;
+    match command {
+        Resize
+        | SetCursorPosition(_, _)
+        | PromptInput(_)
+        | PromptBackspace
+        | PromptSubmit
+        | PromptCancel
+        | PromptComplete
+        | PromptHistoryPrevious
+        | PromptHistoryNext => false,
+        Quit | NoOp => true,
+        Repeat(_, command) => is_bindable(mode, command),
+        ExitHelp => mode == AppMode::Help,
+        ExitGalleryMode | GalleryUp | GalleryDown | GalleryExpand | GalleryCollapse
+        | GallerySelect => mode == AppMode::PatternGallery,
+        ExitBreakpointsMode
+        | BreakpointUp
+        | BreakpointDown
+        | ToggleBreakpoint
+        | RemoveBreakpoint
+        | AddRegionBreakpoint
+        | AddPeriodicBreakpoint => mode == AppMode::Breakpoints,
+        OpenPrompt(PromptAction::GallerySearch) => mode == AppMode::PatternGallery,
+        OpenPrompt(_) => mode == AppMode::Normal || mode == AppMode::Breakpoints,
+        _ => mode == AppMode::Normal,
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(Profile::Standard)
+    }
+}
+
+impl Keymap {
+    fn standard() -> Self {
+        use Command::*;
+        use KeyChord as K;
+
+        let mut normal = vec![
+            bind(K::char('a'), ToggleCellAlive),
+            bind(K::char('b'), MoveCursorToStartOfLine),
+            bind(K::char('B'), EnterBreakpointsMode),
+            bind(K::char('c'), OpenPrompt(PromptAction::ConfirmClear)),
+            bind(K::char('d'), ToggleCellDead),
+            bind(K::new(KeyCode::Backspace), ToggleCellDead),
+            bind(K::char('e'), MoveCursorToEndOfLine),
+            bind(K::char('E'), ExportHistory),
+            bind(K::char('f'), CycleDisplayMode),
+            bind(K::char('g'), EnterGalleryMode),
+            bind(K::char('G'), ToggleChart),
+            bind(K::char('h'), ShowHelp),
+            bind(K::char('i'), SeedSingle),
+            bind(K::char('J'), OpenPrompt(PromptAction::JumpToGeneration)),
+            bind(K::char(':'), OpenPrompt(PromptAction::Command)),
+            bind(K::new(KeyCode::Esc), CancelJump),
+            bind(K::char('l'), PlaceLastPattern),
+            bind(K::char('L'), ToggleLabels),
+            bind(K::char('n'), NoiseUp),
+            bind(K::char('N'), NoiseDown),
+            bind(K::char('m'), CycleEngine),
+            bind(K::char('o'), ToggleCensus),
+            bind(K::char('p'), CyclePatternType),
+            bind(K::char('q'), Quit),
+            bind(K::ctrl('c'), Quit),
+            bind(K::char('r'), RotateLastPattern),
+            bind(K::char('s'), ToggleSimulation),
+            bind(K::char('S'), TogglePauseWhenStable),
+            bind(K::char('v'), ToggleSelection),
+            bind(K::char('x'), SeedRandom),
+            bind(K::char('X'), RegenerateSoup),
+            bind(K::char('y'), CycleSymmetry),
+            bind(K::char('z'), StepBack),
+            bind(K::char('['), PreviousRule),
+            bind(K::char(']'), NextRule),
+            bind(K::char(' '), StepSimulation),
+            bind(K::char('+'), SpeedUp),
+            bind(K::char('-'), SpeedDown),
+            bind(K::char('>'), MoreGensPerFrame),
+            bind(K::char('<'), FewerGensPerFrame),
+            bind(K::new(KeyCode::Left), MoveCursorLeft),
+            bind(K::new(KeyCode::Right), MoveCursorRight),
+            bind(K::new(KeyCode::Up), MoveCursorUp),
+            bind(K::new(KeyCode::Down), MoveCursorDown),
+            bind(K::new(KeyCode::BackTab), MoveCursorLeftBy(4)),
+            bind(K::new(KeyCode::Tab), MoveCursorRightBy(4)),
+        ];
+        for (index, digit) in ('1'..='9').enumerate() {
+            normal.push(bind(K::char(digit), PlacePattern(index)));
+        }
+
+        Self {
+            profile: Profile::Standard,
+            normal,
+            help: vec![
+                bind(K::new(KeyCode::Esc), ExitHelp),
+                bind(K::char('h'), ExitHelp),
+            ],
+            gallery: vec![
+                bind(K::new(KeyCode::Up), GalleryUp),
+                bind(K::new(KeyCode::Down), GalleryDown),
+                bind(K::new(KeyCode::Left), GalleryCollapse),
+                bind(K::new(KeyCode::Right), GalleryExpand),
+                bind(K::new(KeyCode::Enter), GallerySelect),
+                bind(K::char('/'), OpenPrompt(PromptAction::GallerySearch)),
+                bind(K::char('g'), ExitGalleryMode),
+                bind(K::new(KeyCode::Esc), ExitGalleryMode),
+                bind(K::char('q'), Quit),
+                bind(K::ctrl('c'), Quit),
+            ],
+            breakpoints: vec![
+                bind(K::char('>'), OpenPrompt(PromptAction::PopulationAbove)),
+                bind(K::char('<'), OpenPrompt(PromptAction::PopulationBelow)),
+                bind(K::char('n'), OpenPrompt(PromptAction::GenerationReached)),
+                bind(K::char('r'), AddRegionBreakpoint),
+                bind(K::char('p'), AddPeriodicBreakpoint),
+                bind(K::new(KeyCode::Up), BreakpointUp),
+                bind(K::new(KeyCode::Down), BreakpointDown),
+                bind(K::char(' '), ToggleBreakpoint),
+                bind(K::new(KeyCode::Enter), ToggleBreakpoint),
+                bind(K::char('d'), RemoveBreakpoint),
+                bind(K::new(KeyCode::Delete), RemoveBreakpoint),
+                bind(K::new(KeyCode::Backspace), RemoveBreakpoint),
+                bind(K::char('B'), ExitBreakpointsMode),
+                bind(K::new(KeyCode::Esc), ExitBreakpointsMode),
+                bind(K::char('q'), Quit),
+                bind(K::ctrl('c'), Quit),
+            ],
+        }
+    }
+
+    /// The standard keys with vim movement layered on top; the keys it
+    /// takes over move elsewhere (help to `?`, the gallery to `g p`)
+    fn vim() -> Self {
+        use Command::*;
+        use KeyChord as K;
+
+        let mut keymap = Self::standard();
+        keymap.profile = Profile::Vim;
+
+        let replaced = ['h', 'l', 'b', 'g', 'G', 'p'];
+        keymap.normal.retain(|b| match b.keys[0].code {
+            KeyCode::Char(c) => !replaced.contains(&c) && !c.is_ascii_digit(),
+            _ => true,
+        });
+        let mut normal = vec![
+            bind(K::char('h'), MoveCursorLeft),
+            bind(K::char('j'), MoveCursorDown),
+            bind(K::char('k'), MoveCursorUp),
+            bind(K::char('l'), MoveCursorRight),
+            bind(K::char('w'), NextCluster),
+            bind(K::char('b'), PreviousCluster),
+            bind(K::char('0'), MoveCursorToStartOfLine),
+            bind(K::char('$'), MoveCursorToEndOfLine),
+            bind_sequence(&[K::char('g'), K::char('g')], MoveCursorToTop),
+            bind(K::char('G'), MoveCursorToBottom),
+            bind(K::char('.'), RepeatLastEdit),
+            bind(K::char('p'), PlaceLastPattern),
+            bind(K::char('P'), PlacePattern(0)),
+            bind(K::char('t'), CyclePatternType),
+            bind(K::char('?'), ShowHelp),
+            bind_sequence(&[K::char('g'), K::char('p')], EnterGalleryMode),
+            bind_sequence(&[K::char('g'), K::char('c')], ToggleChart),
+        ];
+        normal.append(&mut keymap.normal);
+        keymap.normal = normal;
+
+        keymap.help.push(bind(K::char('?'), ExitHelp));
+        keymap.gallery.extend([
+            bind(K::char('k'), GalleryUp),
+            bind(K::char('j'), GalleryDown),
+            bind(K::char('h'), GalleryCollapse),
+            bind(K::char('l'), GalleryExpand),
+        ]);
+        keymap.breakpoints.extend([
+            bind(K::char('k'), BreakpointUp),
+            bind(K::char('j'), BreakpointDown),
+        ]);
+        keymap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(s: &str) -> KeyChord {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn chords_parse_and_round_trip() {
+        assert_eq!(chord("c"), KeyChord::char('c'));
+        assert_eq!(chord("ctrl+c"), KeyChord::ctrl('c'));
+        assert_eq!(chord("Ctrl+C"), KeyChord::ctrl('C'));
+        assert_eq!(chord("+"), KeyChord::char('+'));
+        assert_eq!(chord("ctrl++").to_string(), "ctrl++");
+        assert_eq!(chord("shift+b"), KeyChord::char('B'));
+        assert_eq!(chord("shift+tab"), KeyChord::new(KeyCode::BackTab));
+        assert_eq!(chord("space"), KeyChord::char(' '));
+        assert_eq!(chord("F12"), KeyChord::new(KeyCode::F(12)));
+        assert_eq!(
+            chord("alt+pagedown").to_string(),
+            "alt+pagedown",
+            "named keys print as they are written"
+        );
+        assert_eq!(parse_keys("g  g").unwrap(), vec![chord("g"), chord("g")]);
+    }
+
+    #[test]
+    fn bad_chords_are_explained() {
+        let error = |s: &str| s.parse::<KeyChord>().unwrap_err();
+        assert_eq!(error("hyper+x"), "unknown modifier 'hyper' in 'hyper+x'");
+        assert_eq!(error("f13"), "unknown key 'f13' in 'f13'");
+        assert_eq!(error("ctrl+"), "unknown key '' in 'ctrl+'");
+        assert_eq!(
+            error("shift+1"),
+            "shift only applies to letters and tab in 'shift+1'; write the shifted character instead"
+        );
+        assert_eq!(parse_keys(" ").unwrap_err(), "empty key");
+    }
+
+    #[test]
+    fn noop_unbinds_and_other_commands_rebind() {
+        let keymap = Keymap::from_json(
+            r#"{"normal": {"c": "NoOp", "ctrl+l": "ClearGrid", "a": "ShowHelp"}}"#,
+            None,
+        )
+        .unwrap();
+        assert!(matches!(
+            keymap.lookup(AppMode::Normal, &[chord("c")]),
+            Lookup::Unbound
+        ));
+        assert!(matches!(
+            keymap.lookup(AppMode::Normal, &[chord("ctrl+l")]),
+            Lookup::Command(Command::ClearGrid)
+        ));
+        assert!(matches!(
+            keymap.lookup(AppMode::Normal, &[chord("a")]),
+            Lookup::Command(Command::ShowHelp)
+        ));
+        // Unbinding a key that was never bound is allowed
+        assert!(Keymap::from_json(r#"{"help": {"F5": "NoOp"}}"#, None).is_ok());
+    }
+
+    #[test]
+    fn bad_files_are_rejected() {
+        let error = |json: &str| Keymap::from_json(json, None).unwrap_err();
+        assert_eq!(
+            error(r#"{"normal": {"q": "NoOp", "ctrl+c": "NoOp"}}"#),
+            "normal mode: no key is bound to Quit"
+        );
+        assert_eq!(
+            error(r#"{"help": {"esc": "NoOp", "h": "NoOp"}}"#),
+            "help mode: no key is bound to ExitHelp"
+        );
+        assert_eq!(
+            error(r#"{"normal": {"x y": "ClearGrid"}}"#),
+            "normal mode: 'x y' can never be typed because 'x' is bound"
+        );
+        assert_eq!(
+            error(r#"{"gallery": {"a": "ClearGrid"}}"#),
+            "gallery mode: ClearGrid cannot be bound in this mode"
+        );
+        assert_eq!(
+            error(r#"{"normal": {"hyper+x": "Quit"}}"#),
+            "normal mode: unknown modifier 'hyper' in 'hyper+x'"
+        );
+        assert_eq!(
+            error(r#"{"normal": {"ctrl+x": "Quit", "Ctrl+x": "Quit"}}"#),
+            "normal mode: 'ctrl+x' is bound more than once"
+        );
+        assert!(error(r#"{"insert": {}}"#).starts_with("unknown field `insert`"));
+        assert!(error(r#"{"normal": {"a": "Fly"}}"#).starts_with("unknown variant `Fly`"));
+    }
+
+    #[test]
+    fn digits_are_counts_in_the_vim_profile() {
+        let json = r#"{"normal": {"5": "ShowHelp"}}"#;
+        assert!(Keymap::from_json(json, Some(Profile::Standard)).is_ok());
+        assert_eq!(
+            Keymap::from_json(json, Some(Profile::Vim)).unwrap_err(),
+            "normal mode: '5' can never be typed because digits are counts in the vim profile"
+        );
+        // 0 is not a count on its own, so it stays bindable
+        assert!(Keymap::from_json(r#"{"normal": {"0": "ShowHelp"}}"#, Some(Profile::Vim)).is_ok());
+    }
+
+    #[test]
+    fn default_profiles_are_valid() {
+        for profile in [Profile::Standard, Profile::Vim] {
+            assert!(Keymap::new(profile).validate().is_ok(), "{}", profile);
+        }
+    }
+}
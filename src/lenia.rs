@@ -0,0 +1,151 @@
+use crate::rng::Rng;
+use crate::size::Size;
+
+/// Parameters of the Lenia update
+#[derive(Clone, Debug)]
+pub struct LeniaParams {
+    /// Kernel radius in cells
+    pub radius: usize,
+    /// Centre of the growth function
+    pub mu: f32,
+    /// Width of the growth function
+    pub sigma: f32,
+    /// Time step per generation
+    pub dt: f32,
+}
+
+/// Continuous-state automaton in the style of Lenia/SmoothLife.
+///
+/// Every cell holds a value in [0, 1]. Each generation the field is convolved
+/// with a smooth ring kernel, passed through a gaussian growth function and
+/// integrated by `dt`. The field wraps around at the grid edges.
+#[derive(Clone)]
+pub struct Lenia {
+    pub params: LeniaParams,
+    field: Vec<f32>,
+    size: Size,
+    kernel: Vec<(isize, isize, f32)>,
+}
+
+impl Lenia {
+    pub fn new(params: LeniaParams) -> Self {
+        let kernel = Self::ring_kernel(params.radius);
+        Self {
+            params,
+            field: Vec::new(),
+            size: Size {
+                width: 0,
+                height: 0,
+            },
+            kernel,
+        }
+    }
+
+    /// Non-zero taps of the normalised ring kernel `exp(4 - 1 / (r (1 - r)))`
+    fn ring_kernel(radius: usize) -> Vec<(isize, isize, f32)> {
+        let radius = radius.max(1) as isize;
+        let mut taps = Vec::new();
+
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let r = ((dx * dx + dy * dy) as f32).sqrt() / radius as f32;
+                if r > 0.0 && r < 1.0 {
+                    let weight = (4.0 - 1.0 / (r * (1.0 - r))).exp();
+                    if weight > f32::EPSILON {
+                        taps.push((dx, dy, weight));
+                    }
+                }
+            }
+        }
+
+        let total: f32 = taps.iter().map(|(_, _, w)| w).sum();
+        for tap in taps.iter_mut() {
+            tap.2 /= total;
+        }
+        taps
+    }
+
+    fn growth(&self, potential: f32) -> f32 {
+        let d = (potential - self.params.mu) / self.params.sigma;
+        2.0 * (-d * d / 2.0).exp() - 1.0
+    }
+
+    /// Match the field to the grid dimensions, clearing it if they changed
+    pub fn resize(&mut self, size: &Size) {
+        if self.size.width != size.width || self.size.height != size.height {
+            self.size = size.clone();
+            self.field = vec![0.0; size.width * size.height];
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.field.iter_mut().for_each(|v| *v = 0.0);
+    }
+
+    pub fn value(&self, x: usize, y: usize) -> f32 {
+        if x < self.size.width && y < self.size.height {
+            self.field[y * self.size.width + x]
+        } else {
+            0.0
+        }
+    }
+
+    /// Fill a disc of kernel radius around a point with random values
+    pub fn seed_blob(&mut self, cx: usize, cy: usize, rng: &mut Rng) {
+        let radius = self.params.radius as isize;
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                if dx * dx + dy * dy > radius * radius {
+                    continue;
+                }
+                let x = cx as isize + dx;
+                let y = cy as isize + dy;
+                if x >= 0
+                    && y >= 0
+                    && (x as usize) < self.size.width
+                    && (y as usize) < self.size.height
+                {
+                    self.field[y as usize * self.size.width + x as usize] = rng.next_f64() as f32;
+                }
+            }
+        }
+    }
+
+    /// Clear the field and scatter random blobs across it
+    pub fn seed_random(&mut self, rng: &mut Rng) {
+        self.clear();
+        let area = self.size.width * self.size.height;
+        let blob_area = (self.params.radius * self.params.radius * 4).max(1);
+        let count = (area / blob_area / 8).max(1);
+        for _ in 0..count {
+            let x = (rng.next_u64() % self.size.width.max(1) as u64) as usize;
+            let y = (rng.next_u64() % self.size.height.max(1) as u64) as usize;
+            self.seed_blob(x, y, rng);
+        }
+    }
+
+    pub fn step(&mut self) {
+        let width = self.size.width as isize;
+        let height = self.size.height as isize;
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let mut next = vec![0.0; self.field.len()];
+        for y in 0..height {
+            for x in 0..width {
+                let mut potential = 0.0;
+                for &(dx, dy, weight) in &self.kernel {
+                    let nx = (x + dx).rem_euclid(width);
+                    let ny = (y + dy).rem_euclid(height);
+                    potential += weight * self.field[(ny * width + nx) as usize];
+                }
+
+                let index = (y * width + x) as usize;
+                let value = self.field[index] + self.params.dt * self.growth(potential);
+                next[index] = value.clamp(0.0, 1.0);
+            }
+        }
+        self.field = next;
+    }
+}
@@ -1,32 +1,66 @@
 use crate::health::Health::Alive;
 use crate::pattern::Pattern;
 use crate::pattern::PatternType;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use std::fs::File;
 use std::io;
 use std::io::Read;
+use std::str::FromStr;
 
 mod app;
+mod bands;
+mod bench;
+mod breakpoint;
+mod census;
+mod command_line;
 mod commands;
 mod coordinates;
+mod elementary;
+mod fuzzy;
 mod grid;
 mod health;
+mod history;
+mod keymap;
+mod lenia;
+mod margolus;
 mod orchestrator;
+mod packed;
 mod pattern;
+mod period;
 mod renderer;
+mod rle;
+mod rng;
+mod rule;
+mod search;
+mod simulation;
 mod size;
+mod soup;
 mod theme;
+mod tiles;
+mod turmite;
 mod user_input;
 mod viewport;
 mod widgets;
+mod worker;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    mode: Option<Mode>,
+
     /// path to a patterns file
-    #[arg(short, long, default_value = "patterns.json")]
+    #[arg(short, long, default_value = "patterns.json", global = true)]
     patterns: String,
 
+    /// path to a keybindings file overriding the default keys (JSON)
+    #[arg(long, default_value = "keybindings.json")]
+    keybindings: String,
+
+    /// default keys to start from: standard, or vim (hjkl, counts, g g/G, w/b, .)
+    #[arg(long)]
+    keymap: Option<keymap::Profile>,
+
     /// make the grid N times bigger than the viewport
     #[arg(short = 'm', long, default_value_t = 3)]
     grid_multiplier: usize,
@@ -38,6 +72,132 @@ struct Args {
     /// maximum grid height (caps the multiplier calculation)
     #[arg(long)]
     grid_max_height: Option<usize>,
+
+    /// life-like rule in B/S notation; a count followed by (p) is stochastic, e.g. B36(0.05)/S23
+    #[arg(long, default_value = "B3/S23")]
+    rule: rule::LifeRule,
+
+    /// random seed for reproducible runs (defaults to the clock)
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// probability of flipping each cell after every generation
+    #[arg(long, default_value_t = 0.0, value_parser = noise_rate)]
+    noise: f64,
+
+    /// how life-like generations are computed: packed (64 cells per word) or scalar
+    #[arg(long, default_value = "packed")]
+    backend: grid::Backend,
+
+    /// threads to split each generation across on large grids (defaults to one per CPU core)
+    #[arg(long)]
+    threads: Option<usize>,
+
+    /// pause the simulation as soon as the universe becomes periodic
+    #[arg(long)]
+    pause_when_stable: bool,
+
+    /// side of the square soup placed at the cursor when nothing is selected
+    #[arg(long, default_value_t = 16)]
+    soup_size: usize,
+
+    /// fraction of soup cells that start alive
+    #[arg(long, default_value_t = 0.5)]
+    soup_density: f64,
+
+    /// soup symmetry in apgsearch notation, e.g. C1, C2_4, D4_+1, D8_1
+    #[arg(long, default_value = "C1")]
+    symmetry: soup::Symmetry,
+
+    /// seed of the first soup, to regenerate a shared soup with X
+    #[arg(long)]
+    soup_seed: Option<u64>,
+
+    /// Wolfram rule (0-255) used by the elementary 1D engine
+    #[arg(long, default_value_t = 30)]
+    elementary_rule: u8,
+
+    /// turmite rule, one turn (L, R, N, U) per cell colour, e.g. RL or LLRR
+    #[arg(long, default_value = "RL")]
+    turmite_rule: turmite::TurmiteRule,
+
+    /// margolus block rule: critters, bbm, tron, or 16 comma-separated block states
+    #[arg(long, default_value = "critters")]
+    margolus_rule: margolus::MargolusRule,
+
+    /// lenia kernel radius in cells
+    #[arg(long, default_value_t = 13, value_parser = lenia_radius)]
+    lenia_radius: usize,
+
+    /// lenia growth function centre
+    #[arg(long, default_value_t = 0.15, value_parser = lenia_mu)]
+    lenia_mu: f32,
+
+    /// lenia growth function width
+    #[arg(long, default_value_t = 0.02, value_parser = lenia_sigma)]
+    lenia_sigma: f32,
+
+    /// lenia time step per generation
+    #[arg(long, default_value_t = 0.1, value_parser = lenia_dt)]
+    lenia_dt: f32,
+}
+
+#[derive(Subcommand, Debug)]
+enum Mode {
+    /// search random soups for objects missing from the known-objects list
+    Search(search::SearchArgs),
+    /// measure generations per second for each engine and grid size
+    Bench(bench::BenchArgs),
+}
+
+/// Parse a command line number, rejecting values outside what `valid`
+/// accepts
+fn parse_checked<T: FromStr>(
+    s: &str,
+    valid: impl Fn(&T) -> bool,
+    expected: &str,
+) -> Result<T, String> {
+    match s.parse() {
+        Ok(value) if valid(&value) => Ok(value),
+        _ => Err(format!("expected {expected}")),
+    }
+}
+
+fn noise_rate(s: &str) -> Result<f64, String> {
+    parse_checked(
+        s,
+        |rate: &f64| (0.0..=1.0).contains(rate),
+        "a probability from 0 to 1",
+    )
+}
+
+/// The ring kernel has no cells inside it below a radius of 2
+fn lenia_radius(s: &str) -> Result<usize, String> {
+    parse_checked(s, |radius| *radius >= 2, "a whole number of at least 2")
+}
+
+fn lenia_mu(s: &str) -> Result<f32, String> {
+    parse_checked(
+        s,
+        |mu: &f32| (0.0..=1.0).contains(mu),
+        "a number from 0 to 1",
+    )
+}
+
+fn lenia_sigma(s: &str) -> Result<f32, String> {
+    parse_checked(
+        s,
+        |sigma: &f32| *sigma > 0.0 && sigma.is_finite(),
+        "a number above 0",
+    )
+}
+
+fn lenia_dt(s: &str) -> Result<f32, String> {
+    parse_checked(
+        s,
+        |dt: &f32| *dt > 0.0 && *dt <= 1.0,
+        "a number above 0 and at most 1",
+    )
 }
 
 fn create_default_pattern() -> Vec<PatternType> {
@@ -65,42 +225,68 @@ fn install_panic_hook() {
     }));
 }
 
-fn main() -> io::Result<()> {
-    install_panic_hook();
-    let args = Args::parse();
-
-    let configuration: Vec<PatternType> = if let Ok(mut file) = File::open(&args.patterns) {
+fn load_configuration(path: &str) -> Vec<PatternType> {
+    if let Ok(mut file) = File::open(path) {
         let mut buff = String::new();
         match file.read_to_string(&mut buff) {
             Ok(_) => match serde_json::from_str(&buff) {
                 Ok(config) => config,
                 Err(e) => {
-                    eprintln!(
-                        "Warning: Failed to parse patterns file '{}': {}",
-                        args.patterns, e
-                    );
+                    eprintln!("Warning: Failed to parse patterns file '{}': {}", path, e);
                     eprintln!("Using default pattern instead.");
                     create_default_pattern()
                 }
             },
             Err(e) => {
-                eprintln!(
-                    "Warning: Failed to read patterns file '{}': {}",
-                    args.patterns, e
-                );
+                eprintln!("Warning: Failed to read patterns file '{}': {}", path, e);
                 eprintln!("Using default pattern instead.");
                 create_default_pattern()
             }
         }
     } else {
-        if args.patterns != "patterns.json" {
+        if path != "patterns.json" {
             eprintln!(
                 "Warning: Could not open patterns file '{}', using default pattern.",
-                args.patterns
+                path
             );
         }
         create_default_pattern()
+    }
+}
+
+/// Load the keybindings file over the default keys, exiting with an error if
+/// it is invalid. A missing default file just means the default keys.
+fn load_keymap(path: &str, profile: Option<keymap::Profile>) -> keymap::Keymap {
+    let json = match std::fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == io::ErrorKind::NotFound && path == "keybindings.json" => {
+            return keymap::Keymap::new(profile.unwrap_or_default());
+        }
+        Err(e) => {
+            eprintln!("Error: Failed to read keybindings file '{}': {}", path, e);
+            std::process::exit(2);
+        }
     };
+    match keymap::Keymap::from_json(&json, profile) {
+        Ok(keymap) => keymap,
+        Err(e) => {
+            eprintln!("Error: Invalid keybindings file '{}': {}", path, e);
+            std::process::exit(2);
+        }
+    }
+}
+
+fn main() -> io::Result<()> {
+    install_panic_hook();
+    let args = Args::parse();
+
+    let configuration = load_configuration(&args.patterns);
+
+    match args.mode {
+        Some(Mode::Search(search_args)) => return search::run(search_args, &configuration),
+        Some(Mode::Bench(bench_args)) => return bench::run(bench_args, &configuration),
+        None => {}
+    }
 
     let grid_config = orchestrator::GridConfig {
         multiplier: args.grid_multiplier,
@@ -108,7 +294,44 @@ fn main() -> io::Result<()> {
         max_height: args.grid_max_height,
     };
 
-    let mut viewer = orchestrator::Orchestrator::init(configuration, grid_config)?;
+    let seed = args.seed.unwrap_or_else(rng::time_seed);
+    let soup = soup::Soup {
+        symmetry: args.symmetry,
+        density: args.soup_density,
+        seed: args
+            .soup_seed
+            .unwrap_or_else(|| rng::Rng::new(seed).next_u64()),
+    };
+
+    let simulation_config = simulation::SimulationConfig {
+        rule: args.rule,
+        seed,
+        noise_rate: args.noise,
+        elementary_rule: args.elementary_rule,
+        turmite_rule: args.turmite_rule,
+        margolus_rule: args.margolus_rule,
+        lenia: lenia::LeniaParams {
+            radius: args.lenia_radius,
+            mu: args.lenia_mu,
+            sigma: args.lenia_sigma,
+            dt: args.lenia_dt,
+        },
+        backend: args.backend,
+        threads: args
+            .threads
+            .or_else(|| std::thread::available_parallelism().ok().map(|n| n.get()))
+            .unwrap_or(1),
+    };
+
+    let run_config = orchestrator::RunConfig {
+        engines: simulation_config,
+        pause_when_stable: args.pause_when_stable,
+        soup,
+        soup_size: args.soup_size,
+        keymap: load_keymap(&args.keybindings, args.keymap),
+    };
+
+    let mut viewer = orchestrator::Orchestrator::init(configuration, grid_config, run_config)?;
     viewer.run()?;
     Ok(())
 }
@@ -0,0 +1,344 @@
+use std::convert::TryInto;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::coordinates::Coordinates;
+use crate::grid::Grid;
+use crate::health::Health::Alive;
+
+/// Preset names cycled with the previous/next rule commands
+const PRESETS: [&str; 3] = ["critters", "bbm", "tron"];
+
+/// Bit of each cell inside a 2x2 block
+const TOP_LEFT: u8 = 1;
+const TOP_RIGHT: u8 = 2;
+const BOTTOM_LEFT: u8 = 4;
+const BOTTOM_RIGHT: u8 = 8;
+
+/// A Margolus block rule: a 16-entry table mapping each 2x2 block state to
+/// its successor. Block states are bit sets of `TOP_LEFT | TOP_RIGHT |
+/// BOTTOM_LEFT | BOTTOM_RIGHT`.
+#[derive(Clone, Debug)]
+pub struct MargolusRule {
+    name: String,
+    table: [u8; 16],
+    inverse: Option<[u8; 16]>,
+}
+
+impl MargolusRule {
+    pub fn from_table(name: &str, table: [u8; 16]) -> Self {
+        Self {
+            name: name.to_string(),
+            table,
+            inverse: Self::invert(&table),
+        }
+    }
+
+    /// The inverse table, if the rule is a permutation of block states
+    fn invert(table: &[u8; 16]) -> Option<[u8; 16]> {
+        let mut inverse = [0u8; 16];
+        let mut seen = [false; 16];
+        for (state, &next) in table.iter().enumerate() {
+            if seen[next as usize] {
+                return None;
+            }
+            seen[next as usize] = true;
+            inverse[next as usize] = state as u8;
+        }
+        Some(inverse)
+    }
+
+    pub fn is_reversible(&self) -> bool {
+        self.inverse.is_some()
+    }
+
+    /// Critters: complement every block without exactly two live cells, and
+    /// rotate blocks that had three live cells by 180 degrees
+    fn critters() -> Self {
+        let table = std::array::from_fn(|state| {
+            let state = state as u8;
+            match state.count_ones() {
+                2 => state,
+                3 => rotate_180(!state & 0xF),
+                _ => !state & 0xF,
+            }
+        });
+        Self::from_table("critters", table)
+    }
+
+    /// Billiard-ball machine: single balls move to the opposite corner and
+    /// head-on diagonal pairs bounce off at right angles
+    fn billiard_ball() -> Self {
+        let table = std::array::from_fn(|state| {
+            let state = state as u8;
+            match state {
+                s if s.count_ones() == 1 => rotate_180(s),
+                s if s == TOP_LEFT | BOTTOM_RIGHT => TOP_RIGHT | BOTTOM_LEFT,
+                s if s == TOP_RIGHT | BOTTOM_LEFT => TOP_LEFT | BOTTOM_RIGHT,
+                s => s,
+            }
+        });
+        Self::from_table("bbm", table)
+    }
+
+    /// Tron: complement empty and full blocks, leave the rest alone
+    fn tron() -> Self {
+        let table = std::array::from_fn(|state| match state as u8 {
+            0 => 0xF,
+            0xF => 0,
+            s => s,
+        });
+        Self::from_table("tron", table)
+    }
+}
+
+fn rotate_180(state: u8) -> u8 {
+    let mut rotated = 0;
+    for (from, to) in [
+        (TOP_LEFT, BOTTOM_RIGHT),
+        (TOP_RIGHT, BOTTOM_LEFT),
+        (BOTTOM_LEFT, TOP_RIGHT),
+        (BOTTOM_RIGHT, TOP_LEFT),
+    ] {
+        if state & from != 0 {
+            rotated |= to;
+        }
+    }
+    rotated
+}
+
+impl FromStr for MargolusRule {
+    type Err = String;
+
+    /// Accepts a preset name or 16 comma-separated block states (0-15)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "critters" => return Ok(Self::critters()),
+            "bbm" | "billiard" => return Ok(Self::billiard_ball()),
+            "tron" => return Ok(Self::tron()),
+            _ => {}
+        }
+
+        let values = s
+            .split(',')
+            .map(|v| {
+                v.trim()
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|v| *v < 16)
+                    .ok_or_else(|| format!("invalid block state '{}', expected 0-15", v.trim()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let table: [u8; 16] = values.try_into().map_err(|_| {
+            format!(
+                "expected one of {} or 16 comma-separated block states",
+                PRESETS.join(", ")
+            )
+        })?;
+
+        Ok(Self::from_table("custom", table))
+    }
+}
+
+impl fmt::Display for MargolusRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+/// Block cellular automaton over the alternating 2x2 Margolus partition.
+///
+/// Even generations use blocks anchored at (0, 0), odd generations blocks
+/// anchored at (1, 1). The partition wraps around the grid edges, so objects
+/// cross them as on a torus. On a side of odd length one row or column is
+/// left out of each partition; every phase still splits the grid into
+/// disjoint blocks, which keeps invertible rules exactly reversible.
+#[derive(Clone)]
+pub struct Margolus {
+    pub rule: MargolusRule,
+    odd_phase: bool,
+}
+
+impl Margolus {
+    pub fn new(rule: MargolusRule) -> Self {
+        Self {
+            rule,
+            odd_phase: false,
+        }
+    }
+
+    pub fn phase_label(&self) -> &'static str {
+        if self.odd_phase {
+            "odd"
+        } else {
+            "even"
+        }
+    }
+
+    pub fn restart(&mut self) {
+        self.odd_phase = false;
+    }
+
+    /// Rules like Critters complement empty blocks, so the background flips
+    /// every generation; showing odd generations inverted keeps it stable
+    pub fn inverted_display(&self) -> bool {
+        self.odd_phase && self.rule.table[0] == 0xF
+    }
+
+    pub fn next_rule(&mut self) {
+        self.cycle_preset(true);
+    }
+
+    pub fn previous_rule(&mut self) {
+        self.cycle_preset(false);
+    }
+
+    /// Move to the neighbouring preset. A custom rule counts as sitting just
+    /// before the first preset and just after the last.
+    fn cycle_preset(&mut self, forward: bool) {
+        let current = self.rule.to_string();
+        let last = PRESETS.len() - 1;
+        let index = match (
+            PRESETS.iter().position(|preset| *preset == current),
+            forward,
+        ) {
+            (Some(i), true) => (i + 1) % PRESETS.len(),
+            (Some(i), false) => (i + last) % PRESETS.len(),
+            (None, true) => 0,
+            (None, false) => last,
+        };
+        if let Ok(rule) = PRESETS[index].parse() {
+            self.rule = rule;
+        }
+    }
+
+    pub fn step(&mut self, grid: &mut Grid) {
+        let table = self.rule.table;
+        Self::apply(grid, &table, self.odd_phase);
+        self.odd_phase = !self.odd_phase;
+    }
+
+    /// Undo one generation. Returns false if the rule is not invertible.
+    pub fn step_back(&mut self, grid: &mut Grid) -> bool {
+        match self.rule.inverse {
+            Some(inverse) => {
+                self.odd_phase = !self.odd_phase;
+                Self::apply(grid, &inverse, self.odd_phase);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn apply(grid: &mut Grid, table: &[u8; 16], odd_phase: bool) {
+        let size = grid.get_size().clone();
+        let offset = odd_phase as usize;
+        let corners = [
+            (0, 0, TOP_LEFT),
+            (1, 0, TOP_RIGHT),
+            (0, 1, BOTTOM_LEFT),
+            (1, 1, BOTTOM_RIGHT),
+        ];
+        let cell = |x: usize, y: usize, dx: usize, dy: usize| Coordinates {
+            x: (x + dx) % size.width,
+            y: (y + dy) % size.height,
+        };
+
+        for row in 0..size.height / 2 {
+            let y = offset + 2 * row;
+            for column in 0..size.width / 2 {
+                let x = offset + 2 * column;
+                let mut state = 0;
+                for (dx, dy, bit) in corners {
+                    if grid.get_cell(cell(x, y, dx, dy)) == Some(Alive) {
+                        state |= bit;
+                    }
+                }
+
+                let next = table[state as usize];
+                if next != state {
+                    for (dx, dy, bit) in corners {
+                        if next & bit != 0 {
+                            grid.resurrect(cell(x, y, dx, dy));
+                        } else {
+                            grid.kill(cell(x, y, dx, dy));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::size::Size;
+
+    fn random_grid(width: usize, height: usize, seed: u64) -> Grid {
+        let mut grid = Grid::new(Size { width, height });
+        let mut rng = Rng::new(seed);
+        for y in 0..height {
+            for x in 0..width {
+                if rng.chance(0.4) {
+                    grid.resurrect(Coordinates { x, y });
+                }
+            }
+        }
+        grid
+    }
+
+    fn cells(grid: &Grid) -> Vec<(usize, usize)> {
+        grid.live_cells().collect()
+    }
+
+    #[test]
+    fn step_back_undoes_steps_on_even_and_odd_grids() {
+        for (width, height) in [(16, 12), (15, 11), (16, 11)] {
+            for rule in ["critters", "bbm", "tron"] {
+                let mut margolus = Margolus::new(rule.parse().unwrap());
+                let mut grid = random_grid(width, height, 7);
+                let start = cells(&grid);
+                for _ in 0..25 {
+                    margolus.step(&mut grid);
+                }
+                assert_ne!(cells(&grid), start, "{} on {}x{}", rule, width, height);
+                for _ in 0..25 {
+                    assert!(margolus.step_back(&mut grid));
+                }
+                assert_eq!(cells(&grid), start, "{} on {}x{}", rule, width, height);
+            }
+        }
+    }
+
+    #[test]
+    fn balls_cross_the_grid_edge() {
+        let mut margolus = Margolus::new("bbm".parse().unwrap());
+        let mut grid = Grid::new(Size {
+            width: 8,
+            height: 6,
+        });
+        grid.resurrect(Coordinates { x: 6, y: 4 });
+        margolus.step(&mut grid);
+        assert_eq!(cells(&grid), vec![(7, 5)]);
+        // The odd block at (7, 5) wraps around to (0, 0)
+        margolus.step(&mut grid);
+        assert_eq!(cells(&grid), vec![(0, 0)]);
+        margolus.step(&mut grid);
+        assert_eq!(cells(&grid), vec![(1, 1)]);
+    }
+
+    #[test]
+    fn custom_rules_cycle_to_the_nearest_end_of_the_presets() {
+        let custom = "0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15";
+        let mut margolus = Margolus::new(custom.parse().unwrap());
+        margolus.next_rule();
+        assert_eq!(margolus.rule.to_string(), PRESETS[0]);
+
+        let mut margolus = Margolus::new(custom.parse().unwrap());
+        margolus.previous_rule();
+        assert_eq!(margolus.rule.to_string(), PRESETS[PRESETS.len() - 1]);
+    }
+}
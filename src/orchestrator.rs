@@ -1,3 +1,5 @@
+use std::collections::VecDeque;
+use std::fs;
 use std::io::{self, Stdout};
 use std::time::{Duration, Instant};
 
@@ -10,7 +12,12 @@ use ratatui::backend::CrosstermBackend;
 use ratatui::prelude::Rect;
 use ratatui::Terminal;
 
-use crate::app::{App, AppMode, GalleryCursor};
+use crate::app::{
+    App, AppMode, DisplayMode, FastForward, GalleryCursor, Prompt, PromptAction, SidePanel,
+};
+use crate::breakpoint::{Breakpoints, Condition};
+use crate::census::{self, Census};
+use crate::command_line::{self, LineCommand};
 
 /// Describes what the run loop should do after handling a command
 pub enum CommandOutcome {
@@ -24,15 +31,44 @@ pub enum CommandOutcome {
 use crate::commands::{Command, CommandHandler};
 use crate::coordinates::Coordinates;
 use crate::grid::Grid;
+use crate::keymap::Keymap;
 use crate::pattern::PatternType;
 use crate::renderer::Renderer;
+use crate::rle;
+use crate::simulation::{Engine, Simulation, SimulationConfig};
 use crate::size::Size;
+use crate::soup::Soup;
 use crate::theme::Theme;
 use crate::user_input;
 use crate::viewport::Viewport;
-use crate::widgets::pattern_gallery::compute_visible_nodes;
+use crate::widgets::pattern_gallery::{compute_visible_nodes, search_nodes};
+use crate::worker::{Goal, Job, StopReason, Update, Worker};
 
 const PATTERN_GALLERY_WIDTH: u16 = 24;
+/// File the population history is exported to
+const HISTORY_CSV: &str = "population.csv";
+/// Shortest time between redraws while the simulation runs (about 30 fps)
+const FRAME_INTERVAL: Duration = Duration::from_millis(33);
+/// Longest wait for input before checking the worker again
+const POLL_INTERVAL: Duration = Duration::from_millis(25);
+/// How often the measured generation and frame rates are refreshed
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+const MAX_GENS_PER_FRAME: u32 = 1024;
+/// Command lines kept for recall with up/down
+const COMMAND_HISTORY_LENGTH: usize = 100;
+
+/// A change to the simulation, deferred while the worker holds it
+type Edit = Box<dyn FnOnce(&mut Orchestrator) -> io::Result<()>>;
+
+/// Startup options for the simulation and run loop
+pub struct RunConfig {
+    pub engines: SimulationConfig,
+    pub pause_when_stable: bool,
+    /// Symmetry, density and first seed for random soups
+    pub soup: Soup,
+    pub soup_size: usize,
+    pub keymap: Keymap,
+}
 
 /// Configuration for grid initialization
 pub struct GridConfig {
@@ -74,18 +110,35 @@ pub struct Orchestrator {
     theme: Theme,
     grid_config: GridConfig,
     last_canvas_area: Option<Rect>,
-    last_tick: Instant,
+    /// Turns key presses into commands using the active keymap
+    commands: CommandHandler,
+    /// Runs the simulation in the background while it advances
+    worker: Worker,
+    /// Edits made while the worker held the simulation, applied in order
+    /// once it hands the simulation back
+    pending_edits: VecDeque<Edit>,
+    last_frame: Instant,
+    /// A new snapshot arrived since the last redraw
+    frame_pending: bool,
+    /// Start of the current rate window and what happened in it
+    rate_start: Instant,
+    rate_generations: u64,
+    rate_frames: u64,
 }
 
 impl Orchestrator {
-    pub fn init(configuration: Vec<PatternType>, grid_config: GridConfig) -> io::Result<Self> {
+    pub fn init(
+        configuration: Vec<PatternType>,
+        grid_config: GridConfig,
+        run_config: RunConfig,
+    ) -> io::Result<Self> {
         let terminal = setup_terminal()?;
         let (grid, size) = init_grid_and_size(&grid_config)?;
         let viewport = Viewport::new(grid.get_size(), size.clone());
         let num_types = configuration.len();
 
         let app = App {
-            grid,
+            simulation: Simulation::new(grid, run_config.engines),
             cursor: Coordinates { x: 0, y: 0 },
             viewport,
             viewport_size: size,
@@ -94,8 +147,29 @@ impl Orchestrator {
             current_pattern_type: 0,
             last_pattern: None,
             simulation_delay: 50,
+            gens_per_frame: 1,
+            gens_per_second: 0.0,
+            frames_per_second: 0.0,
+            pause_when_stable: run_config.pause_when_stable,
             mode: AppMode::Normal,
             gallery_cursor: GalleryCursor::new(num_types),
+            side_panel: SidePanel::Gallery,
+            census: Census::default(),
+            display_mode: DisplayMode::Cells,
+            show_chart: false,
+            message: None,
+            breakpoints: Breakpoints::default(),
+            prompt: None,
+            fast_forward: None,
+            show_labels: false,
+            labels: Vec::new(),
+            labelled: None,
+            selection_anchor: None,
+            soup: run_config.soup,
+            soup_size: run_config.soup_size,
+            keymap: run_config.keymap,
+            pending_keys: String::new(),
+            command_history: Vec::new(),
         };
 
         Ok(Self {
@@ -104,7 +178,14 @@ impl Orchestrator {
             theme: Theme::default(),
             grid_config,
             last_canvas_area: None,
-            last_tick: Instant::now(),
+            commands: CommandHandler::default(),
+            worker: Worker::spawn(),
+            pending_edits: VecDeque::new(),
+            last_frame: Instant::now(),
+            frame_pending: false,
+            rate_start: Instant::now(),
+            rate_generations: 0,
+            rate_frames: 0,
         })
     }
 
@@ -114,19 +195,32 @@ impl Orchestrator {
         self.render()?;
 
         loop {
-            let elapsed = self.last_tick.elapsed().as_millis();
-            if self.app.mode != AppMode::Help
-                && self.app.mode != AppMode::PatternGallery
-                && self.app.running
-                && elapsed > self.app.simulation_delay
-            {
-                self.app.grid.generate();
-                self.last_tick = Instant::now();
+            // The worker advances the simulation on its own clock; redraws
+            // are capped at the frame rate however fast generations come in
+            self.receive_updates()?;
+            self.worker
+                .set_pace(self.app.simulation_delay, self.app.gens_per_frame);
+            match self.worker.goal() {
+                None if self.app.running && self.pending_edits.is_empty() => {
+                    self.start_job(Goal::Continuous)?;
+                }
+                Some(Goal::Continuous) if !self.app.running => self.worker.cancel(),
+                _ => {}
+            }
+            if self.frame_pending && self.last_frame.elapsed() >= FRAME_INTERVAL {
                 self.render()?;
             }
+            self.measure_rates();
 
-            if let Some(event) = user_input::poll_event(Duration::from_millis(25))? {
-                let command = CommandHandler::event_to_command(&event, self.app.mode);
+            if let Some(event) = user_input::poll_event(self.poll_timeout())? {
+                let command =
+                    self.commands
+                        .event_to_command(&event, self.app.mode, &self.app.keymap);
+                let pending_keys = self.commands.pending_keys();
+                if pending_keys != self.app.pending_keys {
+                    self.app.pending_keys = pending_keys;
+                    self.frame_pending = true;
+                }
                 match self.handle_command(command)? {
                     CommandOutcome::Quit => break,
                     CommandOutcome::Render => {
@@ -141,7 +235,168 @@ impl Orchestrator {
         Ok(())
     }
 
+    /// Wait for input until the next snapshot or pending redraw is due
+    fn poll_timeout(&self) -> Duration {
+        let mut timeout = POLL_INTERVAL;
+        if self.worker.is_busy() {
+            timeout = timeout.min(FRAME_INTERVAL);
+        }
+        if self.frame_pending {
+            timeout = timeout.min(FRAME_INTERVAL.saturating_sub(self.last_frame.elapsed()));
+        }
+        timeout
+    }
+
+    /// Refresh the generations and frames per second shown on the footer
+    fn measure_rates(&mut self) {
+        let elapsed = self.rate_start.elapsed();
+        if elapsed < RATE_WINDOW {
+            return;
+        }
+        let seconds = elapsed.as_secs_f64();
+        self.app.gens_per_second = self.rate_generations as f64 / seconds;
+        self.app.frames_per_second = self.rate_frames as f64 / seconds;
+        self.rate_start = Instant::now();
+        self.rate_generations = 0;
+        self.rate_frames = 0;
+    }
+
+    /// Hand the simulation to the worker. The app keeps a copy to draw
+    /// until the first snapshot arrives.
+    fn start_job(&mut self, goal: Goal) -> io::Result<()> {
+        self.sync_elementary_window();
+        self.worker.start(Job {
+            simulation: self.app.simulation.clone(),
+            breakpoints: self.app.breakpoints.clone(),
+            pause_when_stable: self.app.pause_when_stable,
+            goal,
+        })
+    }
+
+    /// Apply an edit to the simulation now, or queue it until the worker
+    /// hands the simulation back. A continuous run is stopped so the edit
+    /// lands within a generation; jumps finish first.
+    fn edit(&mut self, edit: impl FnOnce(&mut Self) -> io::Result<()> + 'static) -> io::Result<()> {
+        if !self.worker.is_busy() && self.pending_edits.is_empty() {
+            return edit(self);
+        }
+        self.pending_edits.push_back(Box::new(edit));
+        if self.worker.goal() == Some(Goal::Continuous) {
+            self.worker.cancel();
+        }
+        Ok(())
+    }
+
+    /// Apply queued edits in order until one of them starts a new job
+    fn apply_pending_edits(&mut self) -> io::Result<()> {
+        while !self.worker.is_busy() {
+            let Some(edit) = self.pending_edits.pop_front() else {
+                break;
+            };
+            edit(self)?;
+            self.frame_pending = true;
+        }
+        Ok(())
+    }
+
+    /// Take in snapshots and the final state from the worker
+    fn receive_updates(&mut self) -> io::Result<()> {
+        while let Some(update) = self.worker.try_recv()? {
+            match update {
+                Update::Snapshot {
+                    snapshot,
+                    generations,
+                    became_stable,
+                } => {
+                    self.app.simulation.show(*snapshot);
+                    self.rate_generations += generations;
+                    if became_stable {
+                        self.on_stable();
+                    }
+                }
+                Update::Progress {
+                    generation,
+                    generations,
+                } => {
+                    if let Some(jump) = &mut self.app.fast_forward {
+                        jump.generation = generation;
+                    }
+                    self.rate_generations += generations;
+                }
+                Update::Stopped {
+                    simulation,
+                    breakpoints,
+                    generations,
+                    became_stable,
+                    reason,
+                } => {
+                    self.app.simulation = *simulation;
+                    self.app.breakpoints.list = breakpoints.list;
+                    self.rate_generations += generations;
+                    if became_stable {
+                        self.on_stable();
+                    }
+                    self.on_stopped(reason);
+                    self.apply_pending_edits()?;
+                }
+            }
+            self.frame_pending = true;
+        }
+        Ok(())
+    }
+
+    fn on_stable(&mut self) {
+        if self.app.side_panel == SidePanel::Census {
+            self.app.take_census();
+        }
+    }
+
+    /// Pause the run if a breakpoint or auto-pause stopped it, and report
+    /// how a jump ended
+    fn on_stopped(&mut self, reason: StopReason) {
+        let generation = self.app.simulation.generation;
+        match reason {
+            StopReason::Breakpoint(id) => {
+                self.app.running = false;
+                self.app.breakpoints.fired = Some(id);
+                if let Some(fired) = self.app.breakpoints.fired() {
+                    self.app.message = Some(format!("breakpoint: {}", fired.condition));
+                }
+            }
+            StopReason::Stable => self.app.running = false,
+            StopReason::Cancelled => {
+                if self.app.fast_forward.is_some() {
+                    self.app.message = Some(format!("jump cancelled at gen {generation}"));
+                }
+            }
+            StopReason::Reached => {
+                if let Some(jump) = &self.app.fast_forward {
+                    self.app.message = Some(format!(
+                        "jumped to gen {} in {:.1}s",
+                        generation,
+                        jump.started.elapsed().as_secs_f64()
+                    ));
+                }
+            }
+        }
+        self.app.fast_forward = None;
+    }
+
+    /// Step in the background to the target generation, with a progress
+    /// indicator on the footer. Esc cancels; breakpoints and auto-pause stop
+    /// the jump early.
+    fn fast_forward(&mut self, target: u64) -> io::Result<()> {
+        self.app.fast_forward = Some(FastForward {
+            start: self.app.simulation.generation,
+            generation: self.app.simulation.generation,
+            target,
+            started: Instant::now(),
+        });
+        self.start_job(Goal::Generation(target))
+    }
+
     fn render(&mut self) -> io::Result<()> {
+        self.app.refresh_labels();
         let mut canvas_area: Option<Rect> = None;
         let app = &self.app;
         let theme = &self.theme;
@@ -155,21 +410,285 @@ impl Orchestrator {
 
         // Update the list state after render (for scroll position)
         self.app.gallery_cursor.list_state = list_state;
+        self.last_frame = Instant::now();
+        self.frame_pending = false;
+        self.rate_frames += 1;
 
         if let Some(area) = canvas_area {
             self.app.viewport_size = Size {
                 width: area.width as usize,
                 height: area.height as usize,
             };
-            self.app
-                .viewport
-                .update_size(self.app.viewport_size.clone(), self.app.grid.get_size());
+            self.app.viewport.update_size(
+                self.app.viewport_size.clone(),
+                self.app.simulation.grid.get_size(),
+            );
+            self.sync_elementary_window();
             self.last_canvas_area = Some(area);
         }
 
         Ok(())
     }
 
+    /// Act on a submitted prompt, reporting bad input on the footer
+    fn submit_prompt(&mut self, prompt: Prompt) -> io::Result<()> {
+        let input = prompt.input.trim();
+        if prompt.action == PromptAction::Command {
+            if input.is_empty() {
+                return Ok(());
+            }
+            if self.app.command_history.last().map(String::as_str) != Some(input) {
+                self.app.command_history.push(input.to_string());
+                if self.app.command_history.len() > COMMAND_HISTORY_LENGTH {
+                    self.app.command_history.remove(0);
+                }
+            }
+            return self.run_command_line(input);
+        }
+        if prompt.action == PromptAction::ConfirmClear {
+            // Answered by the first key typed; enter alone keeps the grid
+            return Ok(());
+        }
+        if prompt.action == PromptAction::GallerySearch {
+            if !input.is_empty() {
+                self.select_search_match(input);
+            }
+            return Ok(());
+        }
+
+        let (relative, digits) = match input.strip_prefix('+') {
+            Some(digits) => (true, digits),
+            None => (false, input),
+        };
+        let value = match digits.parse::<u64>() {
+            Ok(value) if !relative || prompt.action == PromptAction::JumpToGeneration => value,
+            _ => {
+                self.app.message = Some(format!("not a number: '{input}'"));
+                return Ok(());
+            }
+        };
+
+        let condition = match prompt.action {
+            PromptAction::PopulationAbove => Condition::PopulationAbove(value as usize),
+            PromptAction::PopulationBelow => Condition::PopulationBelow(value as usize),
+            PromptAction::GenerationReached => Condition::Generation(value),
+            PromptAction::JumpToGeneration => {
+                let current = self.app.simulation.generation;
+                let target = if relative {
+                    current.saturating_add(value)
+                } else {
+                    value
+                };
+                if target <= current {
+                    self.app.message = Some(format!("already at gen {current}"));
+                    return Ok(());
+                }
+                return self.edit(move |this| this.fast_forward(target));
+            }
+            PromptAction::Command | PromptAction::GallerySearch | PromptAction::ConfirmClear => {
+                unreachable!("command lines, searches and confirmations are handled above")
+            }
+        };
+        self.add_breakpoint(condition)
+    }
+
+    /// Run a `:` command, reporting errors and results on the footer
+    fn run_command_line(&mut self, line: &str) -> io::Result<()> {
+        let command = match line.parse::<LineCommand>() {
+            Ok(command) => command,
+            Err(e) => {
+                self.app.message = Some(e);
+                return Ok(());
+            }
+        };
+
+        match command {
+            LineCommand::Rule(rule) => self.edit(move |this| {
+                this.app.message = Some(match this.app.simulation.set_rule(&rule) {
+                    Ok(()) => {
+                        // Objects are recognised under the rule
+                        this.app.labelled = None;
+                        format!("now running {}", this.app.simulation)
+                    }
+                    Err(e) => e,
+                });
+                Ok(())
+            })?,
+            LineCommand::Goto(position) => {
+                let size = self.app.simulation.grid.get_size();
+                if position.x >= size.width || position.y >= size.height {
+                    self.app.message = Some(format!(
+                        "({}, {}) is outside the {}x{} grid",
+                        position.x, position.y, size.width, size.height
+                    ));
+                } else {
+                    self.move_cursor_to(position);
+                }
+            }
+            LineCommand::Save(path) => {
+                let cells: Vec<(usize, usize)> = self.app.simulation.grid.live_cells().collect();
+                self.app.message = Some(if cells.is_empty() {
+                    String::from("nothing to save: no live cells")
+                } else {
+                    let text = rle::encode(&cells, &self.app.simulation.rule);
+                    match fs::write(&path, text + "\n") {
+                        Ok(()) => format!("saved {} cells to {}", cells.len(), path),
+                        Err(e) => format!("could not save {path}: {e}"),
+                    }
+                });
+            }
+            LineCommand::Load(path) => {
+                let decoded = fs::read_to_string(&path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|text| rle::decode(&text));
+                let decoded = match decoded {
+                    Ok(decoded) => decoded,
+                    Err(e) => {
+                        self.app.message = Some(format!("could not load {path}: {e}"));
+                        return Ok(());
+                    }
+                };
+                let position = self.app.grid_cursor();
+                self.edit(move |this| {
+                    let height = decoded.matrix.len();
+                    let width = decoded.matrix.first().map(Vec::len).unwrap_or(0);
+                    this.app.simulation.grid.shape(position, &decoded.matrix);
+                    let mut message = format!("loaded {path} ({width}x{height})");
+                    if let Some(rule) = decoded.rule {
+                        if rule != this.app.simulation.rule {
+                            message.push_str(&format!(", rule now {rule}"));
+                            this.app.simulation.rule = rule;
+                        }
+                    }
+                    this.app.message = Some(message);
+                    Ok(())
+                })?;
+            }
+            LineCommand::Step(0) => {}
+            LineCommand::Step(generations) => self.edit(move |this| {
+                let target = this.app.simulation.generation.saturating_add(generations);
+                this.fast_forward(target)
+            })?,
+            LineCommand::Speed(gens_per_second) => {
+                // One generation per delay up to the redraw rate, then more
+                // generations per frame
+                let gens_per_second = gens_per_second as u128;
+                let frame_ms = FRAME_INTERVAL.as_millis();
+                if gens_per_second * frame_ms <= 1000 {
+                    self.app.simulation_delay = 1000 / gens_per_second;
+                    self.app.gens_per_frame = 1;
+                } else {
+                    self.app.simulation_delay = frame_ms;
+                    self.app.gens_per_frame =
+                        (gens_per_second * frame_ms / 1000).min(MAX_GENS_PER_FRAME as u128) as u32;
+                }
+                self.app.message = Some(format!(
+                    "delay {}ms x{}",
+                    self.app.simulation_delay, self.app.gens_per_frame
+                ));
+            }
+            LineCommand::Random(density) => {
+                if let Some(density) = density {
+                    self.app.soup.density = density;
+                }
+                self.handle_command(Command::SeedRandom)?;
+            }
+            LineCommand::Theme(name) => {
+                if let Some(theme) = Theme::named(&name) {
+                    self.theme = theme;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Complete the command line, or show the next candidate when there
+    /// are several
+    fn complete_prompt(&mut self) {
+        let Some(prompt) = self.app.prompt.as_mut() else {
+            return;
+        };
+        if prompt.action != PromptAction::Command {
+            return;
+        }
+        if prompt.completions.is_empty() {
+            prompt.completions = command_line::completions(&prompt.input);
+            prompt.completion = 0;
+        } else {
+            prompt.completion = (prompt.completion + 1) % prompt.completions.len();
+        }
+        if let Some(line) = prompt.completions.get(prompt.completion) {
+            prompt.input = line.clone();
+        }
+        // A single candidate is final; the next tab completes from it
+        if prompt.completions.len() == 1 {
+            prompt.completions.clear();
+        }
+    }
+
+    /// Show an earlier (or later) command line in the prompt
+    fn recall_history(&mut self, earlier: bool) {
+        let Some(prompt) = self.app.prompt.as_mut() else {
+            return;
+        };
+        let history = &self.app.command_history;
+        if prompt.action != PromptAction::Command || history.is_empty() {
+            return;
+        }
+        prompt.history_index = match (prompt.history_index, earlier) {
+            (None, true) => Some(history.len() - 1),
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) if index + 1 < history.len() => Some(index + 1),
+            (_, false) => None,
+        };
+        prompt.input = prompt
+            .history_index
+            .map(|index| history[index].clone())
+            .unwrap_or_default();
+        prompt.completions.clear();
+    }
+
+    fn add_breakpoint(&mut self, condition: Condition) -> io::Result<()> {
+        self.edit(move |this| {
+            this.app.breakpoints.add(condition, &this.app.simulation);
+            Ok(())
+        })
+    }
+
+    /// The selection, or a square of the soup size at the cursor
+    fn soup_area(&self) -> (Coordinates, Size) {
+        self.app.selection().unwrap_or_else(|| {
+            (
+                self.app.grid_cursor(),
+                Size {
+                    width: self.app.soup_size,
+                    height: self.app.soup_size,
+                },
+            )
+        })
+    }
+
+    /// Fill an area with the current soup
+    fn place_soup(&mut self, (position, size): (Coordinates, Size)) {
+        let cells = self.app.soup.generate(size.width, size.height);
+        self.app.simulation.grid.shape(position, &cells);
+    }
+
+    /// The elementary engine writes its history into the visible rows of the grid
+    fn sync_elementary_window(&mut self) {
+        let top = self.app.viewport.y_offset();
+        let rows = self.app.viewport_size.height.min(
+            self.app
+                .simulation
+                .grid
+                .get_size()
+                .height
+                .saturating_sub(top),
+        );
+        self.app.simulation.elementary.set_window(top, rows);
+        self.worker.set_window(top, rows);
+    }
+
     fn center_cursor(&mut self) {
         let max_x = self.max_cursor_x();
         let max_y = self.max_cursor_y();
@@ -194,6 +713,7 @@ impl Orchestrator {
     fn max_cursor_x(&self) -> usize {
         let grid_limit = self
             .app
+            .simulation
             .grid
             .get_size()
             .width
@@ -209,6 +729,7 @@ impl Orchestrator {
     fn max_cursor_y(&self) -> usize {
         let grid_limit = self
             .app
+            .simulation
             .grid
             .get_size()
             .height
@@ -248,6 +769,50 @@ impl Orchestrator {
         self.app.cursor.x = (self.app.cursor.x + amount).min(max_x);
     }
 
+    /// Move the cursor to a grid cell, panning the viewport just enough to
+    /// show it
+    fn move_cursor_to(&mut self, target: Coordinates) {
+        let grid_size = self.app.simulation.grid.get_size().clone();
+        let x = target.x.min(grid_size.width.saturating_sub(1));
+        let y = target.y.min(grid_size.height.saturating_sub(1));
+        let view = self.app.viewport_size.clone();
+        let viewport = &mut self.app.viewport;
+
+        if x < viewport.x_offset() {
+            viewport.pan_left(viewport.x_offset() - x, grid_size.width);
+        } else if x >= viewport.x_offset() + view.width {
+            viewport.pan_right(x + 1 - view.width - viewport.x_offset(), grid_size.width);
+        }
+        if y < viewport.y_offset() {
+            viewport.pan_up(viewport.y_offset() - y, grid_size.height);
+        } else if y >= viewport.y_offset() + view.height {
+            viewport.pan_down(y + 1 - view.height - viewport.y_offset(), grid_size.height);
+        }
+
+        self.app.cursor.x = x.saturating_sub(self.app.viewport.x_offset());
+        self.app.cursor.y = y.saturating_sub(self.app.viewport.y_offset());
+        self.clamp_cursor();
+    }
+
+    /// Jump to the top-left cell of the next (or previous) island of live
+    /// cells in reading order, like `w`/`b` between words
+    fn jump_to_cluster(&mut self, forward: bool) {
+        let cursor = self.app.grid_cursor();
+        let cursor = (cursor.y, cursor.x);
+        let starts = census::islands(&self.app.simulation.grid)
+            .into_iter()
+            .filter_map(|island| island.into_iter().map(|(x, y)| (y, x)).min());
+        let target = if forward {
+            starts.filter(|start| *start > cursor).min()
+        } else {
+            starts.filter(|start| *start < cursor).max()
+        };
+        match target {
+            Some((y, x)) => self.move_cursor_to(Coordinates { x, y }),
+            None => self.app.message = Some(String::from("no more clusters")),
+        }
+    }
+
     fn move_cur_down(&mut self) {
         let max_y = self.max_cursor_y();
         if self.app.cursor.y < max_y {
@@ -374,6 +939,40 @@ impl Orchestrator {
         }
     }
 
+    /// Move the gallery cursor to the best match while a search is typed
+    fn follow_gallery_search(&mut self) {
+        let Some(query) = self.app.gallery_search() else {
+            return;
+        };
+        let nodes = search_nodes(&self.app, query);
+        let top = nodes
+            .iter()
+            .find(|n| n.pattern_idx.is_some())
+            .or(nodes.first());
+        if let Some(top) = top {
+            let cursor = &mut self.app.gallery_cursor;
+            cursor.pattern_type_idx = top.type_idx;
+            cursor.pattern_idx = top.pattern_idx;
+            // Keep the match visible once the search is left
+            if let Some(expanded) = cursor.expanded_types.get_mut(top.type_idx) {
+                *expanded = true;
+            }
+        }
+    }
+
+    /// Select the best pattern matching a submitted search
+    fn select_search_match(&mut self, query: &str) {
+        let nodes = search_nodes(&self.app, query);
+        match nodes.iter().find(|n| n.pattern_idx.is_some()) {
+            Some(top) => {
+                self.app.gallery_cursor.pattern_type_idx = top.type_idx;
+                self.app.gallery_cursor.pattern_idx = top.pattern_idx;
+                self.gallery_select();
+            }
+            None => self.app.message = Some(format!("no pattern matches '{query}'")),
+        }
+    }
+
     fn gallery_select(&mut self) {
         let cursor = &self.app.gallery_cursor;
 
@@ -395,6 +994,9 @@ impl Orchestrator {
 
     fn handle_command(&mut self, command: Command) -> io::Result<CommandOutcome> {
         let grid_position = self.app.grid_cursor();
+        if !matches!(command, Command::NoOp | Command::Resize) {
+            self.app.message = None;
+        }
 
         match command {
             Command::Quit => return Ok(CommandOutcome::Quit),
@@ -406,16 +1008,20 @@ impl Orchestrator {
                 return Ok(CommandOutcome::Handled);
             }
             Command::ClearGrid => {
-                let (grid, size) = init_grid_and_size(&self.grid_config)?;
-                self.app.grid = grid;
-                self.app.viewport_size = size;
-                self.app
-                    .viewport
-                    .update_size(self.app.viewport_size.clone(), self.app.grid.get_size());
-                // Render first to get the correct canvas dimensions
-                self.render()?;
-                // Now center the cursor based on the actual canvas size
-                self.center_cursor();
+                self.edit(|this| {
+                    let (grid, size) = init_grid_and_size(&this.grid_config)?;
+                    this.app.simulation.reset(grid);
+                    this.app.viewport_size = size;
+                    this.app.viewport.update_size(
+                        this.app.viewport_size.clone(),
+                        this.app.simulation.grid.get_size(),
+                    );
+                    // Render first to get the correct canvas dimensions
+                    this.render()?;
+                    // Now center the cursor based on the actual canvas size
+                    this.center_cursor();
+                    Ok(())
+                })?;
                 return Ok(CommandOutcome::Handled);
             }
             Command::MoveCursorLeft => self.move_cur_left(),
@@ -428,12 +1034,32 @@ impl Orchestrator {
             Command::MoveCursorToEndOfLine => {
                 self.app.cursor.x = self.max_cursor_x();
             }
+            Command::MoveCursorToTop => self.move_cursor_to(Coordinates {
+                x: grid_position.x,
+                y: 0,
+            }),
+            Command::MoveCursorToBottom => self.move_cursor_to(Coordinates {
+                x: grid_position.x,
+                y: self.app.simulation.grid.get_size().height,
+            }),
+            Command::MoveCursorToRow(y) => self.move_cursor_to(Coordinates {
+                x: grid_position.x,
+                y,
+            }),
+            Command::NextCluster => self.jump_to_cluster(true),
+            Command::PreviousCluster => self.jump_to_cluster(false),
             Command::ToggleCellAlive => {
-                self.app.grid.resurrect(grid_position);
+                self.edit(move |this| {
+                    this.app.simulation.grid.resurrect(grid_position);
+                    Ok(())
+                })?;
                 self.move_cur_right();
             }
             Command::ToggleCellDead => {
-                self.app.grid.kill(grid_position);
+                self.edit(move |this| {
+                    this.app.simulation.grid.kill(grid_position);
+                    Ok(())
+                })?;
                 self.move_cur_left();
             }
             Command::PlaceLastPattern => {
@@ -445,7 +1071,11 @@ impl Orchestrator {
                         .map(|p| &p.patterns)
                     {
                         if let Some(pattern) = patterns.get(index) {
-                            self.app.grid.shape(grid_position, &pattern.matrix);
+                            let matrix = pattern.matrix.clone();
+                            self.edit(move |this| {
+                                this.app.simulation.grid.shape(grid_position, &matrix);
+                                Ok(())
+                            })?;
                         }
                     }
                 }
@@ -463,10 +1093,78 @@ impl Orchestrator {
             }
             Command::ToggleSimulation => {
                 self.app.running = !self.app.running;
+                if self.app.running {
+                    self.app.breakpoints.fired = None;
+                }
+            }
+            Command::TogglePauseWhenStable => {
+                self.app.pause_when_stable = !self.app.pause_when_stable;
             }
             Command::StepSimulation => {
-                self.app.grid.generate();
-                self.last_tick = Instant::now();
+                self.edit(|this| {
+                    let next = this.app.simulation.generation + 1;
+                    this.start_job(Goal::Generation(next))
+                })?;
+            }
+            Command::CycleEngine => {
+                self.edit(|this| {
+                    this.app.simulation.cycle_engine();
+                    Ok(())
+                })?;
+            }
+            Command::NextRule => {
+                self.edit(|this| {
+                    this.app.simulation.next_rule();
+                    Ok(())
+                })?;
+            }
+            Command::PreviousRule => {
+                self.edit(|this| {
+                    this.app.simulation.previous_rule();
+                    Ok(())
+                })?;
+            }
+            Command::SeedSingle => {
+                self.edit(move |this| {
+                    this.app.simulation.seed_single(grid_position);
+                    Ok(())
+                })?;
+            }
+            Command::SeedRandom => {
+                let area = self.soup_area();
+                self.edit(move |this| {
+                    if this.app.simulation.engine == Engine::Life {
+                        this.app.soup.seed = this.app.simulation.rng.next_u64();
+                        this.place_soup(area);
+                    } else {
+                        this.app.simulation.seed_random();
+                    }
+                    Ok(())
+                })?;
+            }
+            Command::RegenerateSoup => {
+                let area = self.soup_area();
+                self.edit(move |this| {
+                    if this.app.simulation.engine == Engine::Life {
+                        this.place_soup(area);
+                    }
+                    Ok(())
+                })?;
+            }
+            Command::CycleSymmetry => {
+                self.app.soup.symmetry = self.app.soup.symmetry.next();
+            }
+            Command::ToggleSelection => {
+                self.app.selection_anchor = match self.app.selection_anchor {
+                    Some(_) => None,
+                    None => Some(grid_position.clone()),
+                };
+            }
+            Command::StepBack => {
+                self.edit(|this| {
+                    this.app.simulation.step_back();
+                    Ok(())
+                })?;
             }
             Command::SpeedUp => {
                 if let Some(val) = self.app.simulation_delay.checked_sub(10) {
@@ -478,15 +1176,40 @@ impl Orchestrator {
                     self.app.simulation_delay = val;
                 }
             }
+            Command::CancelJump => {
+                if self.app.fast_forward.is_some() {
+                    self.worker.cancel();
+                }
+            }
+            Command::MoreGensPerFrame => {
+                self.app.gens_per_frame = (self.app.gens_per_frame * 2).min(MAX_GENS_PER_FRAME);
+            }
+            Command::FewerGensPerFrame => {
+                self.app.gens_per_frame = (self.app.gens_per_frame / 2).max(1);
+            }
+            Command::NoiseUp => {
+                self.edit(|this| {
+                    this.app.simulation.noise_up();
+                    Ok(())
+                })?;
+            }
+            Command::NoiseDown => {
+                self.edit(|this| {
+                    this.app.simulation.noise_down();
+                    Ok(())
+                })?;
+            }
             Command::PlacePattern(index) => {
                 if let Some(pattern_type) =
                     self.app.configuration.get(self.app.current_pattern_type)
                 {
                     if index < pattern_type.patterns.len() {
                         self.app.last_pattern = Some(index);
-                        self.app
-                            .grid
-                            .shape(grid_position, &pattern_type.patterns[index].matrix);
+                        let matrix = pattern_type.patterns[index].matrix.clone();
+                        self.edit(move |this| {
+                            this.app.simulation.grid.shape(grid_position, &matrix);
+                            Ok(())
+                        })?;
                     }
                 }
             }
@@ -501,6 +1224,120 @@ impl Orchestrator {
             }
             Command::EnterGalleryMode => {
                 self.app.mode = AppMode::PatternGallery;
+                self.app.side_panel = SidePanel::Gallery;
+            }
+            Command::ToggleChart => {
+                self.app.show_chart = !self.app.show_chart;
+            }
+            Command::ExportHistory => {
+                self.app.message = Some(match self.app.simulation.history.write_csv(HISTORY_CSV) {
+                    Ok(rows) => format!("wrote {rows} generations to {HISTORY_CSV}"),
+                    Err(e) => format!("could not write {HISTORY_CSV}: {e}"),
+                });
+            }
+            Command::CycleDisplayMode => {
+                self.app.display_mode = self.app.display_mode.next();
+            }
+            Command::ToggleLabels => {
+                self.app.show_labels = !self.app.show_labels;
+            }
+            Command::ToggleCensus => {
+                self.app.side_panel = match self.app.side_panel {
+                    SidePanel::Census => SidePanel::Gallery,
+                    SidePanel::Gallery | SidePanel::Breakpoints => {
+                        self.app.take_census();
+                        SidePanel::Census
+                    }
+                };
+            }
+            Command::Repeat(count, command) => {
+                if let Command::StepSimulation = *command {
+                    self.edit(move |this| {
+                        let target = this.app.simulation.generation + count as u64;
+                        this.start_job(Goal::Generation(target))
+                    })?;
+                } else {
+                    for _ in 0..count {
+                        if let CommandOutcome::Quit = self.handle_command((*command).clone())? {
+                            return Ok(CommandOutcome::Quit);
+                        }
+                    }
+                }
+            }
+            // Resolved by the command handler
+            Command::RepeatLastEdit => {}
+            Command::EnterBreakpointsMode => {
+                self.app.mode = AppMode::Breakpoints;
+                self.app.side_panel = SidePanel::Breakpoints;
+            }
+            Command::ExitBreakpointsMode => {
+                self.app.mode = AppMode::Normal;
+            }
+            Command::BreakpointUp => self.app.breakpoints.select_previous(),
+            Command::BreakpointDown => self.app.breakpoints.select_next(),
+            Command::ToggleBreakpoint => {
+                let index = self.app.breakpoints.selected;
+                self.edit(move |this| {
+                    this.app.breakpoints.toggle(index);
+                    Ok(())
+                })?;
+            }
+            Command::RemoveBreakpoint => {
+                let index = self.app.breakpoints.selected;
+                self.edit(move |this| {
+                    this.app.breakpoints.remove(index);
+                    Ok(())
+                })?;
+            }
+            Command::AddRegionBreakpoint => match self.app.selection() {
+                Some((position, size)) => {
+                    self.add_breakpoint(Condition::RegionChanged { position, size })?
+                }
+                None => {
+                    self.app.message = Some(String::from("mark a selection with v first"));
+                }
+            },
+            Command::AddPeriodicBreakpoint => self.add_breakpoint(Condition::Periodic)?,
+            Command::OpenPrompt(action) => {
+                self.app.prompt = Some(Prompt::new(action, self.app.mode));
+                self.app.mode = AppMode::Prompt;
+            }
+            Command::PromptInput(c) => {
+                let confirming = self.app.prompt.as_ref().map(|prompt| prompt.action)
+                    == Some(PromptAction::ConfirmClear);
+                if confirming {
+                    if let Some(prompt) = self.app.prompt.take() {
+                        self.app.mode = prompt.return_mode;
+                    }
+                    if c.eq_ignore_ascii_case(&'y') {
+                        return self.handle_command(Command::ClearGrid);
+                    }
+                } else if let Some(prompt) = self.app.prompt.as_mut() {
+                    prompt.input.push(c);
+                    prompt.completions.clear();
+                }
+                self.follow_gallery_search();
+            }
+            Command::PromptBackspace => {
+                if let Some(prompt) = self.app.prompt.as_mut() {
+                    prompt.input.pop();
+                    prompt.completions.clear();
+                }
+                self.follow_gallery_search();
+            }
+            Command::PromptComplete => self.complete_prompt(),
+            Command::PromptHistoryPrevious => self.recall_history(true),
+            Command::PromptHistoryNext => self.recall_history(false),
+            Command::PromptSubmit => {
+                if let Some(prompt) = self.app.prompt.take() {
+                    self.app.mode = prompt.return_mode;
+                    self.submit_prompt(prompt)?;
+                }
+            }
+            Command::PromptCancel => {
+                if let Some(prompt) = self.app.prompt.take() {
+                    self.app.mode = prompt.return_mode;
+                }
             }
             Command::ExitGalleryMode => {
                 self.app.mode = AppMode::Normal;
@@ -0,0 +1,190 @@
+use crate::bands::for_each_band;
+use crate::rule::LifeRule;
+
+pub const WORD_BITS: usize = 64;
+
+/// Cells packed 64 to a word, row by row, least significant bit first.
+/// Bits past the right edge of the grid are always zero.
+#[derive(Clone)]
+pub struct PackedRows {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    words: Vec<u64>,
+}
+
+impl PackedRows {
+    pub fn new(width: usize, height: usize) -> Self {
+        let words_per_row = width.div_ceil(WORD_BITS);
+        Self {
+            width,
+            height,
+            words_per_row,
+            words: vec![0u64; words_per_row * height],
+        }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, alive: bool) {
+        let word = &mut self.words[y * self.words_per_row + x / WORD_BITS];
+        let bit = 1 << (x % WORD_BITS);
+        if alive {
+            *word |= bit;
+        } else {
+            *word &= !bit;
+        }
+    }
+
+    /// Shift the rows in `top..bottom` up by one; the first row wraps to the bottom
+    pub fn rotate_rows_left(&mut self, top: usize, bottom: usize) {
+        self.words[top * self.words_per_row..bottom * self.words_per_row]
+            .rotate_left(self.words_per_row);
+    }
+
+    fn row(&self, y: usize) -> &[u64] {
+        &self.words[y * self.words_per_row..(y + 1) * self.words_per_row]
+    }
+
+    /// Mask of the valid cells in the last word of a row
+    fn last_word_mask(&self) -> u64 {
+        match self.width % WORD_BITS {
+            0 => u64::MAX,
+            bits => (1 << bits) - 1,
+        }
+    }
+
+    /// Compute the next generation of a deterministic life-like rule. Cells
+    /// outside the grid are dead, as in the scalar path. Bands of
+    /// `band_rows` rows are computed on separate threads, and words for which
+    /// `is_active(w, y)` is false are copied unchanged.
+    pub fn step<F>(&self, rule: &LifeRule, band_rows: usize, is_active: F) -> Self
+    where
+        F: Fn(usize, usize) -> bool + Sync,
+    {
+        let births: Vec<usize> = (0..9).filter(|&n| rule.birth[n] >= 1.0).collect();
+        let survivals: Vec<usize> = (0..9).filter(|&n| rule.survival[n] >= 1.0).collect();
+        let mut words = vec![0u64; self.words.len()];
+
+        for_each_band(&mut words, band_rows * self.words_per_row, |index, band| {
+            let first_row = index * band_rows;
+            let empty = vec![0u64; self.words_per_row];
+            for (offset, out) in band.chunks_mut(self.words_per_row).enumerate() {
+                let y = first_row + offset;
+                self.step_row(y, &empty, &births, &survivals, out, &is_active);
+            }
+        });
+
+        Self {
+            width: self.width,
+            height: self.height,
+            words_per_row: self.words_per_row,
+            words,
+        }
+    }
+
+    /// Write the next generation of row `y` into `out`; `empty` stands in
+    /// for the rows beyond the top and bottom edges
+    fn step_row(
+        &self,
+        y: usize,
+        empty: &[u64],
+        births: &[usize],
+        survivals: &[usize],
+        out: &mut [u64],
+        is_active: &impl Fn(usize, usize) -> bool,
+    ) {
+        let above = if y > 0 { self.row(y - 1) } else { empty };
+        let current = self.row(y);
+        let below = if y + 1 < self.height {
+            self.row(y + 1)
+        } else {
+            empty
+        };
+
+        for (w, next) in out.iter_mut().enumerate() {
+            if !is_active(w, y) {
+                *next = current[w];
+                continue;
+            }
+
+            // Bit-sliced 4-bit neighbour count, one counter per cell
+            let mut sum = [0u64; 4];
+            for (row, include_centre) in [(above, true), (current, false), (below, true)] {
+                let (west, centre, east) = neighbours(row, w);
+                add(&mut sum, west);
+                add(&mut sum, east);
+                if include_centre {
+                    add(&mut sum, centre);
+                }
+            }
+
+            let alive = current[w];
+            *next = 0;
+            for &n in births {
+                *next |= count_equals(&sum, n) & !alive;
+            }
+            for &n in survivals {
+                *next |= count_equals(&sum, n) & alive;
+            }
+            if w + 1 == self.words_per_row {
+                *next &= self.last_word_mask();
+            }
+        }
+    }
+
+    /// Cells that differ from another packed grid of the same size, as
+    /// (x, y, now alive)
+    pub fn differences<'a>(
+        &'a self,
+        other: &'a PackedRows,
+    ) -> impl Iterator<Item = (usize, usize, bool)> + 'a {
+        self.words
+            .iter()
+            .zip(&other.words)
+            .enumerate()
+            .filter(|(_, (a, b))| a != b)
+            .flat_map(move |(i, (a, b))| {
+                let (y, w) = (i / self.words_per_row, i % self.words_per_row);
+                let mut diff = a ^ b;
+                std::iter::from_fn(move || {
+                    if diff == 0 {
+                        return None;
+                    }
+                    let bit = diff.trailing_zeros() as usize;
+                    diff &= diff - 1;
+                    Some((w * WORD_BITS + bit, y, b & (1 << bit) != 0))
+                })
+            })
+    }
+}
+
+/// The west, centre and east neighbours of every cell in word `w` of a row
+fn neighbours(row: &[u64], w: usize) -> (u64, u64, u64) {
+    let centre = row[w];
+    let previous = if w > 0 { row[w - 1] } else { 0 };
+    let next = row.get(w + 1).copied().unwrap_or(0);
+    let west = (centre << 1) | (previous >> (WORD_BITS - 1));
+    let east = (centre >> 1) | (next << (WORD_BITS - 1));
+    (west, centre, east)
+}
+
+/// Add one bit per cell to a bit-sliced counter (ripple-carry, saturating at 8)
+fn add(sum: &mut [u64; 4], bits: u64) {
+    let carry0 = sum[0] & bits;
+    sum[0] ^= bits;
+    let carry1 = sum[1] & carry0;
+    sum[1] ^= carry0;
+    let carry2 = sum[2] & carry1;
+    sum[2] ^= carry1;
+    sum[3] |= carry2;
+}
+
+/// Mask of the cells whose counter equals `n`
+fn count_equals(sum: &[u64; 4], n: usize) -> u64 {
+    (0..4).fold(u64::MAX, |mask, bit| {
+        if n & (1 << bit) != 0 {
+            mask & sum[bit]
+        } else {
+            mask & !sum[bit]
+        }
+    })
+}
@@ -0,0 +1,188 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use crate::grid::Grid;
+
+/// Generations remembered before the history is discarded
+const MAX_HISTORY: usize = 10_000;
+
+/// A detected cycle of the whole universe
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stability {
+    pub period: u64,
+    /// Generation at which the cycle started
+    pub since: u64,
+    /// Displacement of the live cells over one period
+    pub dx: isize,
+    pub dy: isize,
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl Stability {
+    /// Speed in the usual `c/4 diagonal` notation, or None if not moving
+    pub fn speed(&self) -> Option<String> {
+        let (ax, ay) = (self.dx.unsigned_abs() as u64, self.dy.unsigned_abs() as u64);
+        if ax == 0 && ay == 0 {
+            return None;
+        }
+
+        let direction = if ax == 0 || ay == 0 {
+            "orthogonal"
+        } else if ax == ay {
+            "diagonal"
+        } else {
+            "oblique"
+        };
+
+        let distance = ax.max(ay);
+        let divisor = gcd(distance, self.period);
+        let (distance, period) = (distance / divisor, self.period / divisor);
+        let numerator = if distance == 1 {
+            "c".to_string()
+        } else {
+            format!("{distance}c")
+        };
+
+        Some(if period == 1 {
+            format!("{numerator} {direction}")
+        } else {
+            format!("{numerator}/{period} {direction}")
+        })
+    }
+}
+
+impl fmt::Display for Stability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.speed() {
+            Some(speed) => write!(f, "{speed}, p{} since gen {}", self.period, self.since),
+            None if self.period == 1 => write!(f, "still since gen {}", self.since),
+            None => write!(f, "p{} since gen {}", self.period, self.since),
+        }
+    }
+}
+
+/// Hashes every generation to find when the universe becomes periodic.
+///
+/// Two histories are kept: one of absolute cell positions (oscillators and
+/// still lifes) and one relative to the live bounding box, which also catches
+/// spaceships and reports their displacement.
+#[derive(Clone, Default)]
+pub struct PeriodDetector {
+    absolute: HashMap<u64, u64>,
+    relative: HashMap<u64, (u64, isize, isize)>,
+    revision: u64,
+    stability: Option<Stability>,
+}
+
+impl PeriodDetector {
+    pub fn stability(&self) -> Option<Stability> {
+        self.stability
+    }
+
+    /// Report a stability found by a copy of this detector elsewhere
+    pub fn show(&mut self, stability: Option<Stability>) {
+        self.stability = stability;
+    }
+
+    pub fn reset(&mut self) {
+        self.absolute.clear();
+        self.relative.clear();
+        self.stability = None;
+    }
+
+    /// Start a fresh history from the current grid if it was edited since the
+    /// last observation (or nothing has been observed yet)
+    pub fn sync(&mut self, grid: &Grid, generation: u64) {
+        if grid.revision() != self.revision || self.absolute.is_empty() {
+            self.reset();
+            self.observe(grid, generation);
+        }
+    }
+
+    /// Record the grid at the given generation
+    pub fn observe(&mut self, grid: &Grid, generation: u64) {
+        self.revision = grid.revision();
+        if self.stability.is_some() {
+            return;
+        }
+        if self.absolute.len() >= MAX_HISTORY {
+            self.absolute.clear();
+            self.relative.clear();
+        }
+
+        let cells: Vec<(usize, usize)> = grid.live_cells().collect();
+        let min_x = cells.iter().map(|(x, _)| *x).min().unwrap_or(0);
+        let min_y = cells.iter().map(|(_, y)| *y).min().unwrap_or(0);
+
+        let mut absolute = DefaultHasher::new();
+        let mut relative = DefaultHasher::new();
+        for (x, y) in &cells {
+            (x, y).hash(&mut absolute);
+            (x - min_x, y - min_y).hash(&mut relative);
+        }
+        let (absolute, relative) = (absolute.finish(), relative.finish());
+
+        if let Some(&start) = self.absolute.get(&absolute) {
+            self.stability = Some(Stability {
+                period: generation - start,
+                since: start,
+                dx: 0,
+                dy: 0,
+            });
+        } else if let Some(&(start, x, y)) = self.relative.get(&relative) {
+            self.stability = Some(Stability {
+                period: generation - start,
+                since: start,
+                dx: min_x as isize - x,
+                dy: min_y as isize - y,
+            });
+        } else {
+            self.absolute.insert(absolute, generation);
+            self.relative
+                .insert(relative, (generation, min_x as isize, min_y as isize));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinates::Coordinates;
+    use crate::rng::Rng;
+    use crate::rule::LifeRule;
+    use crate::size::Size;
+
+    #[test]
+    fn glider_moves_one_cell_diagonally_every_four_generations() {
+        let rule: LifeRule = "B3/S23".parse().unwrap();
+        let mut grid = Grid::new(Size {
+            width: 20,
+            height: 20,
+        });
+        for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)] {
+            grid.resurrect(Coordinates { x: x + 2, y: y + 2 });
+        }
+
+        let mut rng = Rng::new(0);
+        let mut detector = PeriodDetector::default();
+        detector.sync(&grid, 0);
+        for generation in 1..=8 {
+            grid.generate(&rule, &mut rng);
+            detector.observe(&grid, generation);
+        }
+
+        let stability = detector.stability().expect("glider was not detected");
+        assert_eq!((stability.period, stability.since), (4, 0));
+        assert_eq!((stability.dx, stability.dy), (1, 1));
+        assert_eq!(stability.speed().as_deref(), Some("c/4 diagonal"));
+    }
+}
@@ -1,13 +1,17 @@
 use ratatui::prelude::*;
 use ratatui::widgets::ListState;
 
-use crate::app::{App, AppMode};
+use crate::app::{App, AppMode, SidePanel};
 use crate::theme::Theme;
 use crate::widgets::{
-    footer_bar::FooterBar, game_canvas::GameCanvas, header_bar::HeaderBar, help_popup::HelpPopup,
-    pattern_gallery::PatternGallery,
+    breakpoints_panel::BreakpointsPanel, census_panel::CensusPanel, footer_bar::FooterBar,
+    game_canvas::GameCanvas, header_bar::HeaderBar, help_popup::HelpPopup,
+    pattern_gallery::PatternGallery, population_chart::PopulationChart,
 };
 
+/// Rows taken by the population chart when it is shown
+const CHART_HEIGHT: u16 = 12;
+
 pub struct RenderOutcome {
     pub canvas_area: Rect,
 }
@@ -25,25 +29,41 @@ impl Renderer {
         let layout = Layout::vertical([
             Constraint::Length(3), // Header (border + content + border)
             Constraint::Fill(1),   // Body
-            Constraint::Length(3), // Footer
+            Constraint::Length(4), // Footer (border + status + simulation + border)
         ])
         .split(frame.area());
 
         let body_layout = Layout::horizontal([
             Constraint::Fill(1),               // Canvas
-            Constraint::Length(gallery_width), // Pattern Gallery
+            Constraint::Length(gallery_width), // Pattern Gallery / Census
         ])
         .split(layout[1]);
 
-        let canvas_area = body_layout[0];
+        let canvas_area = if app.show_chart {
+            let column = Layout::vertical([
+                Constraint::Fill(1),              // Canvas
+                Constraint::Length(CHART_HEIGHT), // Population chart
+            ])
+            .split(body_layout[0]);
+            frame.render_widget(PopulationChart::new(app, theme), column[1]);
+            column[0]
+        } else {
+            body_layout[0]
+        };
 
         frame.render_widget(HeaderBar::new(app, theme), layout[0]);
         frame.render_widget(GameCanvas::new(app, theme), canvas_area);
-        frame.render_stateful_widget(
-            PatternGallery::new(app, theme),
-            body_layout[1],
-            gallery_list_state,
-        );
+        match app.side_panel {
+            SidePanel::Gallery => frame.render_stateful_widget(
+                PatternGallery::new(app, theme),
+                body_layout[1],
+                gallery_list_state,
+            ),
+            SidePanel::Census => frame.render_widget(CensusPanel::new(app, theme), body_layout[1]),
+            SidePanel::Breakpoints => {
+                frame.render_widget(BreakpointsPanel::new(app, theme), body_layout[1])
+            }
+        }
         frame.render_widget(FooterBar::new(app, theme), layout[2]);
 
         if app.mode == AppMode::Help {
@@ -0,0 +1,209 @@
+use std::collections::HashSet;
+
+use crate::health::Health;
+use crate::rule::LifeRule;
+
+/// Longest line in the body of an encoded pattern
+const LINE_LENGTH: usize = 70;
+/// Widest and tallest pattern accepted by `decode`
+const MAX_SIDE: usize = 10_000;
+
+/// Append a run, merging it with the previous one when the tags match
+fn push_run(runs: &mut Vec<(usize, char)>, count: usize, tag: char) {
+    match runs.last_mut() {
+        Some((previous, last)) if *last == tag => *previous += count,
+        _ => runs.push((count, tag)),
+    }
+}
+
+/// Encode live cells in the RLE format used by Golly and the LifeWiki
+pub fn encode(cells: &[(usize, usize)], rule: &LifeRule) -> String {
+    let min_x = cells.iter().map(|(x, _)| *x).min().unwrap_or(0);
+    let min_y = cells.iter().map(|(_, y)| *y).min().unwrap_or(0);
+    let width = cells.iter().map(|(x, _)| x - min_x + 1).max().unwrap_or(0);
+    let height = cells.iter().map(|(_, y)| y - min_y + 1).max().unwrap_or(0);
+    let alive: HashSet<(usize, usize)> =
+        cells.iter().map(|(x, y)| (x - min_x, y - min_y)).collect();
+
+    // (count, tag) runs; trailing dead cells of a row are never written
+    let mut runs: Vec<(usize, char)> = Vec::new();
+
+    for y in 0..height {
+        if y > 0 {
+            push_run(&mut runs, 1, '$');
+        }
+        let mut x = 0;
+        while x < width {
+            let state = alive.contains(&(x, y));
+            let start = x;
+            while x < width && alive.contains(&(x, y)) == state {
+                x += 1;
+            }
+            if state {
+                push_run(&mut runs, x - start, 'o');
+            } else if x < width {
+                push_run(&mut runs, x - start, 'b');
+            }
+        }
+    }
+    runs.push((1, '!'));
+
+    let mut body = String::new();
+    let mut line = String::new();
+    for (count, tag) in runs {
+        let token = if count == 1 {
+            tag.to_string()
+        } else {
+            format!("{count}{tag}")
+        };
+        if line.len() + token.len() > LINE_LENGTH {
+            body.push_str(&line);
+            body.push('\n');
+            line.clear();
+        }
+        line.push_str(&token);
+    }
+    body.push_str(&line);
+
+    format!("x = {width}, y = {height}, rule = {rule}\n{body}")
+}
+
+/// A pattern read from RLE
+pub struct Decoded {
+    pub matrix: Vec<Vec<Health>>,
+    /// The rule named in the header, if any
+    pub rule: Option<LifeRule>,
+}
+
+/// Decode a pattern in the RLE format. `#` comment lines are skipped and
+/// any state other than `b` counts as alive.
+pub fn decode(text: &str) -> Result<Decoded, String> {
+    let mut rule = None;
+    let mut cells: Vec<(usize, usize)> = Vec::new();
+    let (mut x, mut y): (usize, usize) = (0, 0);
+    let mut count: Option<usize> = None;
+
+    'lines: for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('x') {
+            for field in line.split(',') {
+                if let Some(("rule", value)) =
+                    field.split_once('=').map(|(k, v)| (k.trim(), v.trim()))
+                {
+                    rule = Some(
+                        value
+                            .parse::<LifeRule>()
+                            .map_err(|e| format!("bad rule '{value}': {e}"))?,
+                    );
+                }
+            }
+            continue;
+        }
+        for c in line.chars() {
+            if let Some(digit) = c.to_digit(10) {
+                count = Some(
+                    count
+                        .unwrap_or(0)
+                        .saturating_mul(10)
+                        .saturating_add(digit as usize),
+                );
+                continue;
+            }
+            let run = count.take().unwrap_or(1);
+            // Rows and columns are numbered from 0, so the last one is MAX_SIDE - 1
+            let too_large = match c {
+                '$' => y.saturating_add(run) >= MAX_SIDE,
+                '!' => false,
+                _ => x.saturating_add(run) > MAX_SIDE,
+            };
+            if too_large {
+                return Err(format!("pattern larger than {MAX_SIDE} cells across"));
+            }
+            match c {
+                '!' => break 'lines,
+                '$' => {
+                    y += run;
+                    x = 0;
+                }
+                'b' | '.' => x += run,
+                c if c.is_ascii_alphabetic() => {
+                    cells.extend((x..x + run).map(|cx| (cx, y)));
+                    x += run;
+                }
+                c if c.is_whitespace() => {}
+                c => return Err(format!("unexpected '{c}' in RLE body")),
+            }
+        }
+    }
+
+    if cells.is_empty() {
+        return Err(String::from("no live cells in pattern"));
+    }
+    let width = cells.iter().map(|(x, _)| x + 1).max().unwrap_or(0);
+    let height = cells.iter().map(|(_, y)| y + 1).max().unwrap_or(0);
+    let mut matrix = vec![vec![Health::Dead; width]; height];
+    for (x, y) in cells {
+        matrix[y][x] = Health::Alive;
+    }
+    Ok(Decoded { matrix, rule })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    fn live_cells(matrix: &[Vec<Health>]) -> Vec<(usize, usize)> {
+        let mut cells = Vec::new();
+        for (y, row) in matrix.iter().enumerate() {
+            for (x, health) in row.iter().enumerate() {
+                if matches!(health, Health::Alive) {
+                    cells.push((x, y));
+                }
+            }
+        }
+        cells
+    }
+
+    #[test]
+    fn decode_reads_back_what_encode_writes() {
+        let rule: LifeRule = "B36/S23".parse().unwrap();
+        // Wide enough for the body to be wrapped over several lines
+        let mut rng = Rng::new(3);
+        let mut cells: Vec<(usize, usize)> = (0..120)
+            .flat_map(|x| (0..12).map(move |y| (x, y)))
+            .filter(|_| rng.chance(0.3))
+            .collect();
+        cells.extend([(0, 0), (119, 11)]);
+        cells.sort_by_key(|(x, y)| (*y, *x));
+        cells.dedup();
+
+        let text = encode(&cells, &rule);
+        assert!(text
+            .lines()
+            .all(|line| line.len() <= LINE_LENGTH || line.starts_with('x')));
+        let decoded = decode(&text).unwrap();
+        assert_eq!(live_cells(&decoded.matrix), cells);
+        assert_eq!(decoded.rule, Some(rule));
+    }
+
+    #[test]
+    fn decode_rejects_patterns_over_the_maximum_side() {
+        for text in [
+            format!("{}o!", MAX_SIDE + 1),
+            format!("o{}$o!", MAX_SIDE),
+            "99999999999999999999999999o!".to_string(),
+        ] {
+            assert_eq!(
+                decode(&text).err().as_deref(),
+                Some("pattern larger than 10000 cells across"),
+                "{text}"
+            );
+        }
+        assert!(decode(&format!("{}bo!", MAX_SIDE - 1)).is_ok());
+        assert!(decode(&format!("o{}$o!", MAX_SIDE - 1)).is_ok());
+    }
+}
@@ -0,0 +1,102 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// A life-like (outer totalistic) rule in B/S notation.
+///
+/// Each neighbour count 0-8 has a birth and a survival probability. Plain
+/// rules like `B3/S23` use only 0 and 1; a count followed by a probability in
+/// parentheses makes it stochastic, e.g. `B36(0.05)/S23`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LifeRule {
+    pub birth: [f64; 9],
+    pub survival: [f64; 9],
+}
+
+impl LifeRule {
+    pub fn is_deterministic(&self) -> bool {
+        self.birth
+            .iter()
+            .chain(self.survival.iter())
+            .all(|p| *p == 0.0 || *p == 1.0)
+    }
+
+    fn parse_counts(part: &str) -> Result<[f64; 9], String> {
+        let mut counts = [0.0; 9];
+        let mut chars = part.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            let count = c
+                .to_digit(10)
+                .filter(|d| *d <= 8)
+                .ok_or_else(|| format!("invalid neighbour count '{c}'"))?
+                as usize;
+
+            let mut probability = 1.0;
+            if chars.peek() == Some(&'(') {
+                chars.next();
+                let mut value = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == ')' {
+                        closed = true;
+                        break;
+                    }
+                    value.push(c);
+                }
+                if !closed {
+                    return Err(format!("unclosed '(' after neighbour count {count}"));
+                }
+                probability = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|p| (0.0..=1.0).contains(p))
+                    .ok_or_else(|| format!("invalid probability '{value}'"))?;
+            }
+            counts[count] = probability;
+        }
+
+        Ok(counts)
+    }
+
+    fn format_counts(f: &mut fmt::Formatter<'_>, counts: &[f64; 9]) -> fmt::Result {
+        for (count, probability) in counts.iter().enumerate() {
+            if *probability == 1.0 {
+                write!(f, "{count}")?;
+            } else if *probability > 0.0 {
+                write!(f, "{count}({probability})")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for LifeRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (birth, survival) = s
+            .split_once('/')
+            .ok_or_else(|| format!("expected B.../S... rule, got '{s}'"))?;
+
+        let birth = birth
+            .strip_prefix(['B', 'b'])
+            .ok_or_else(|| format!("birth part '{birth}' must start with B"))?;
+        let survival = survival
+            .strip_prefix(['S', 's'])
+            .ok_or_else(|| format!("survival part '{survival}' must start with S"))?;
+
+        Ok(Self {
+            birth: Self::parse_counts(birth)?,
+            survival: Self::parse_counts(survival)?,
+        })
+    }
+}
+
+impl fmt::Display for LifeRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("B")?;
+        Self::format_counts(f, &self.birth)?;
+        f.write_str("/S")?;
+        Self::format_counts(f, &self.survival)
+    }
+}
@@ -0,0 +1,303 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::census::{self, Classification, ObjectKind};
+use crate::coordinates::Coordinates;
+use crate::grid::Grid;
+use crate::pattern::PatternType;
+use crate::period::PeriodDetector;
+use crate::rle;
+use crate::rng::{self, Rng};
+use crate::rule::LifeRule;
+use crate::size::Size;
+use crate::soup::{Soup, Symmetry};
+
+/// Empty space around a soup before the absorbing border
+const SEARCH_MARGIN: usize = 64;
+/// Width of the border band where escaping objects are collected and removed
+const SINK_WIDTH: usize = 8;
+/// Generations between checks of the border band
+const SINK_INTERVAL: u64 = 8;
+/// Soups that have not stabilised by now are censused as they are
+const MAX_GENERATIONS: u64 = 20_000;
+
+/// Options for `rustmaton search`
+#[derive(clap::Args, Debug)]
+pub struct SearchArgs {
+    /// life-like rule in B/S notation
+    #[arg(long, default_value = "B3/S23")]
+    rule: LifeRule,
+
+    /// soup symmetry in apgsearch notation, e.g. C1, C2_4, D4_+1, D8_1
+    #[arg(long, default_value = "C1")]
+    symmetry: Symmetry,
+
+    /// number of soups to search
+    #[arg(long, default_value_t = 1000)]
+    soups: u64,
+
+    /// side of each square soup
+    #[arg(long, default_value_t = 16)]
+    soup_size: usize,
+
+    /// fraction of soup cells that start alive
+    #[arg(long, default_value_t = 0.5)]
+    soup_density: f64,
+
+    /// base seed; soup seeds are derived from it (defaults to the clock)
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// worker threads (defaults to one per CPU core)
+    #[arg(long)]
+    threads: Option<usize>,
+
+    /// file of known apgcodes, one per line, in addition to the gallery patterns
+    #[arg(long, default_value = "known_objects.txt")]
+    known: String,
+}
+
+/// An object that came out of a soup
+struct Find {
+    classification: Classification,
+    cells: Vec<(usize, usize)>,
+    soup_seed: u64,
+}
+
+/// Messages from the workers to the logging thread
+enum Report {
+    /// A soup finished; its objects not already known
+    Soup(Vec<Find>),
+    /// A worker finished; its object counts over all of its soups
+    Done(HashMap<String, (ObjectKind, u64)>),
+}
+
+/// Run a headless soup search, logging unknown objects as RLE on stdout
+pub fn run(args: SearchArgs, configuration: &[PatternType]) -> io::Result<()> {
+    let mut known: HashSet<String> = census::known_objects(&args.rule, configuration)
+        .into_keys()
+        .collect();
+    match File::open(&args.known) {
+        Ok(file) => {
+            for line in BufReader::new(file).lines() {
+                let line = line?;
+                match line.split_whitespace().next() {
+                    Some(code) if !code.starts_with('#') => {
+                        known.insert(code.to_string());
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Err(e) if args.known != "known_objects.txt" => {
+            eprintln!(
+                "Warning: Could not open known objects file '{}': {}",
+                args.known, e
+            );
+        }
+        Err(_) => {}
+    }
+
+    let base_seed = args.seed.unwrap_or_else(rng::time_seed);
+    let threads = args
+        .threads
+        .or_else(|| thread::available_parallelism().ok().map(|n| n.get()))
+        .unwrap_or(1)
+        .max(1);
+    eprintln!(
+        "searching {} {} soups in {} with {} threads, base seed {}, {} known objects",
+        args.soups,
+        args.symmetry,
+        args.rule,
+        threads,
+        base_seed,
+        known.len()
+    );
+
+    // Soup seeds are consecutive from a hashed base, so nearby base seeds
+    // still give unrelated soups
+    let first_soup_seed = Rng::new(base_seed).next_u64();
+    let next_soup = AtomicU64::new(0);
+    let (sender, receiver) = mpsc::channel();
+    let started = Instant::now();
+
+    thread::scope(|scope| -> io::Result<()> {
+        for _ in 0..threads {
+            let sender = sender.clone();
+            let (args, known, next_soup) = (&args, &known, &next_soup);
+            scope.spawn(move || {
+                let mut counts: HashMap<String, (ObjectKind, u64)> = HashMap::new();
+                let mut classified: HashMap<String, Classification> = HashMap::new();
+
+                loop {
+                    let index = next_soup.fetch_add(1, Ordering::Relaxed);
+                    if index >= args.soups {
+                        break;
+                    }
+                    let soup = Soup {
+                        symmetry: args.symmetry,
+                        density: args.soup_density,
+                        seed: Rng::new(first_soup_seed.wrapping_add(index)).next_u64(),
+                    };
+
+                    let mut finds = Vec::new();
+                    for cells in run_soup(&soup, args.soup_size, &args.rule) {
+                        if classified.len() >= census::MAX_CLASSIFIED {
+                            classified.clear();
+                        }
+                        let classification = classified
+                            .entry(census::canonical_code(&cells))
+                            .or_insert_with(|| census::classify(&cells, &args.rule))
+                            .clone();
+                        counts
+                            .entry(classification.apgcode.clone())
+                            .or_insert((classification.kind.clone(), 0))
+                            .1 += 1;
+                        if classification.kind != ObjectKind::Unstable
+                            && !known.contains(&classification.apgcode)
+                        {
+                            finds.push(Find {
+                                classification,
+                                cells,
+                                soup_seed: soup.seed,
+                            });
+                        }
+                    }
+                    if sender.send(Report::Soup(finds)).is_err() {
+                        break;
+                    }
+                }
+                let _ = sender.send(Report::Done(counts));
+            });
+        }
+        drop(sender);
+
+        let mut logged: HashSet<String> = HashSet::new();
+        let mut totals: HashMap<String, (ObjectKind, u64)> = HashMap::new();
+        let mut searched = 0u64;
+        let mut last_progress = Instant::now();
+        let stdout = io::stdout();
+        let mut out = stdout.lock();
+
+        for report in receiver {
+            match report {
+                Report::Soup(finds) => {
+                    searched += 1;
+                    for find in finds {
+                        if !logged.insert(find.classification.apgcode.clone()) {
+                            continue;
+                        }
+                        writeln!(
+                            out,
+                            "#C {} ({}) from soup seed {} ({}, {}x{}, density {})",
+                            find.classification.apgcode,
+                            find.classification.kind,
+                            find.soup_seed,
+                            args.symmetry,
+                            args.soup_size,
+                            args.soup_size,
+                            args.soup_density
+                        )?;
+                        writeln!(out, "{}", rle::encode(&find.cells, &args.rule))?;
+                        out.flush()?;
+                    }
+                    if last_progress.elapsed() >= Duration::from_secs(1) {
+                        last_progress = Instant::now();
+                        eprint!(
+                            "\r{}/{} soups, {:.1} soups/s, {} new objects",
+                            searched,
+                            args.soups,
+                            searched as f64 / started.elapsed().as_secs_f64(),
+                            logged.len()
+                        );
+                    }
+                }
+                Report::Done(counts) => {
+                    for (code, (kind, count)) in counts {
+                        totals.entry(code).or_insert((kind, 0)).1 += count;
+                    }
+                }
+            }
+        }
+
+        let mut totals: Vec<(String, (ObjectKind, u64))> = totals.into_iter().collect();
+        totals.sort_by(|a, b| b.1 .1.cmp(&a.1 .1).then_with(|| a.0.cmp(&b.0)));
+        eprintln!(
+            "\nsearched {} soups in {:.1}s, {} new objects",
+            searched,
+            started.elapsed().as_secs_f64(),
+            logged.len()
+        );
+        for (code, (kind, count)) in totals.iter().take(20) {
+            eprintln!("{count:>10}  {code} ({kind})");
+        }
+        Ok(())
+    })
+}
+
+/// Run one soup until it stabilises and return its objects as islands of
+/// live cells. Objects that reach the border band (escaping spaceships) are
+/// collected there and removed so they cannot wreck themselves on the edge.
+fn run_soup(soup: &Soup, soup_size: usize, rule: &LifeRule) -> Vec<Vec<(usize, usize)>> {
+    let side = soup_size + 2 * SEARCH_MARGIN;
+    let mut grid = Grid::new(Size {
+        width: side,
+        height: side,
+    });
+    grid.shape(
+        Coordinates {
+            x: SEARCH_MARGIN,
+            y: SEARCH_MARGIN,
+        },
+        &soup.generate(soup_size, soup_size),
+    );
+
+    let mut rng = Rng::new(soup.seed);
+    let mut detector = PeriodDetector::default();
+    let mut objects = Vec::new();
+    detector.sync(&grid, 0);
+
+    for generation in 1..=MAX_GENERATIONS {
+        grid.generate(rule, &mut rng);
+        if generation % SINK_INTERVAL == 0 && absorb_escapees(&mut grid, &mut objects) {
+            detector.sync(&grid, generation);
+        } else {
+            detector.observe(&grid, generation);
+        }
+        if detector.stability().is_some() {
+            break;
+        }
+    }
+
+    objects.extend(census::islands(&grid));
+    objects
+}
+
+/// Move islands touching the border band from the grid into `objects`
+fn absorb_escapees(grid: &mut Grid, objects: &mut Vec<Vec<(usize, usize)>>) -> bool {
+    let size = grid.get_size().clone();
+    let in_band = |x: usize, y: usize| {
+        x < SINK_WIDTH
+            || y < SINK_WIDTH
+            || x + SINK_WIDTH >= size.width
+            || y + SINK_WIDTH >= size.height
+    };
+    if !grid.live_cells().any(|(x, y)| in_band(x, y)) {
+        return false;
+    }
+
+    for island in census::islands(grid) {
+        if island.iter().any(|(x, y)| in_band(*x, *y)) {
+            for (x, y) in &island {
+                grid.kill(Coordinates { x: *x, y: *y });
+            }
+            objects.push(island);
+        }
+    }
+    true
+}
@@ -0,0 +1,363 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::coordinates::Coordinates;
+use crate::elementary::Elementary;
+use crate::grid::{Backend, Change, Grid};
+use crate::history::PopulationHistory;
+use crate::lenia::{Lenia, LeniaParams};
+use crate::margolus::{Margolus, MargolusRule};
+use crate::period::{PeriodDetector, Stability};
+use crate::rng::Rng;
+use crate::rule::LifeRule;
+use crate::turmite::{TurmiteRule, Turmites};
+
+/// The rule set used to advance the simulation
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    /// Life-like B/S rules (Conway's Game of Life by default) over the whole grid
+    Life,
+    /// Wolfram elementary 1D automaton, one row per generation
+    Elementary,
+    /// Langton's ant and other turmites
+    Turmite,
+    /// Reversible 2x2 block rules on the Margolus partition
+    Margolus,
+    /// Continuous-state Lenia field
+    Lenia,
+}
+
+impl Engine {
+    pub fn next(self) -> Self {
+        match self {
+            Engine::Life => Engine::Elementary,
+            Engine::Elementary => Engine::Turmite,
+            Engine::Turmite => Engine::Margolus,
+            Engine::Margolus => Engine::Lenia,
+            Engine::Lenia => Engine::Life,
+        }
+    }
+}
+
+/// Configuration for the simulation engines
+pub struct SimulationConfig {
+    pub rule: LifeRule,
+    pub seed: u64,
+    pub noise_rate: f64,
+    pub elementary_rule: u8,
+    pub turmite_rule: TurmiteRule,
+    pub margolus_rule: MargolusRule,
+    pub lenia: LeniaParams,
+    pub backend: Backend,
+    pub threads: usize,
+}
+
+/// Simulation state: the grid plus everything needed to advance it
+#[derive(Clone)]
+pub struct Simulation {
+    pub grid: Grid,
+    pub engine: Engine,
+    pub generation: u64,
+    pub rule: LifeRule,
+    /// Probability of flipping each cell after every life generation
+    pub noise_rate: f64,
+    pub seed: u64,
+    pub elementary: Elementary,
+    pub turmites: Turmites,
+    pub margolus: Margolus,
+    pub lenia: Lenia,
+    pub period: PeriodDetector,
+    pub rng: Rng,
+    /// Births and deaths of the last life generation, noise included
+    changes: Vec<Change>,
+    /// Grid revision the changes lead to; any later edit makes them stale
+    changes_revision: u64,
+    pub history: PopulationHistory,
+}
+
+/// What the run loop needs to draw a simulation running on the worker:
+/// the grid and engine state, without the period hashes, and only the
+/// population history recorded since the previous snapshot
+pub struct Snapshot {
+    grid: Grid,
+    generation: u64,
+    elementary: Elementary,
+    turmites: Turmites,
+    margolus: Margolus,
+    /// The Lenia field, only when that engine is running
+    lenia: Option<Lenia>,
+    rng: Rng,
+    changes: Vec<Change>,
+    changes_revision: u64,
+    stability: Option<Stability>,
+    history: Vec<(u64, usize)>,
+}
+
+impl Simulation {
+    pub fn new(mut grid: Grid, config: SimulationConfig) -> Self {
+        grid.set_backend(config.backend);
+        grid.set_threads(config.threads);
+        let mut lenia = Lenia::new(config.lenia);
+        lenia.resize(grid.get_size());
+
+        Self {
+            grid,
+            engine: Engine::Life,
+            generation: 0,
+            rule: config.rule,
+            noise_rate: config.noise_rate,
+            seed: config.seed,
+            elementary: Elementary::new(config.elementary_rule),
+            turmites: Turmites::new(config.turmite_rule),
+            margolus: Margolus::new(config.margolus_rule),
+            lenia,
+            period: PeriodDetector::default(),
+            rng: Rng::new(config.seed),
+            changes: Vec::new(),
+            changes_revision: 0,
+            history: PopulationHistory::default(),
+        }
+    }
+
+    /// Replace the grid (e.g. on clear) and restart the generation count
+    pub fn reset(&mut self, mut grid: Grid) {
+        grid.set_backend(self.grid.backend());
+        grid.set_threads(self.grid.threads());
+        grid.succeed(&self.grid);
+        self.grid = grid;
+        self.generation = 0;
+        self.elementary.restart();
+        self.turmites.clear();
+        self.margolus.restart();
+        self.lenia.resize(self.grid.get_size());
+        self.lenia.clear();
+        self.period.reset();
+        self.changes.clear();
+        self.history.clear();
+    }
+
+    /// A copy of the drawn state, with the history after `generation`
+    pub fn snapshot(&self, generation: Option<u64>) -> Snapshot {
+        Snapshot {
+            grid: self.grid.clone(),
+            generation: self.generation,
+            elementary: self.elementary.clone(),
+            turmites: self.turmites.clone(),
+            margolus: self.margolus.clone(),
+            lenia: (self.engine == Engine::Lenia).then(|| self.lenia.clone()),
+            rng: self.rng.clone(),
+            changes: self.changes.clone(),
+            changes_revision: self.changes_revision,
+            stability: self.period.stability(),
+            history: self.history.points_after(generation),
+        }
+    }
+
+    /// Take on the state of a snapshot of this simulation taken elsewhere
+    pub fn show(&mut self, snapshot: Snapshot) {
+        self.grid = snapshot.grid;
+        self.generation = snapshot.generation;
+        self.elementary = snapshot.elementary;
+        self.turmites = snapshot.turmites;
+        self.margolus = snapshot.margolus;
+        if let Some(lenia) = snapshot.lenia {
+            self.lenia = lenia;
+        }
+        self.rng = snapshot.rng;
+        self.changes = snapshot.changes;
+        self.changes_revision = snapshot.changes_revision;
+        self.period.show(snapshot.stability);
+        for (generation, population) in snapshot.history {
+            self.history.record(generation, population);
+        }
+    }
+
+    pub fn cycle_engine(&mut self) {
+        self.engine = self.engine.next();
+        self.elementary.restart();
+        self.margolus.restart();
+    }
+
+    /// Advance the simulation by one generation
+    pub fn step(&mut self) {
+        if self.history.is_empty() {
+            self.history.record(self.generation, self.grid.population());
+        }
+
+        match self.engine {
+            Engine::Life => {
+                self.period.sync(&self.grid, self.generation);
+                self.changes = self.grid.generate(&self.rule, &mut self.rng);
+                let flips = self.grid.apply_noise(self.noise_rate, &mut self.rng);
+                self.add_flips(flips);
+            }
+            Engine::Elementary => self.elementary.step(&mut self.grid),
+            Engine::Turmite => self.turmites.step(&mut self.grid),
+            Engine::Margolus => self.margolus.step(&mut self.grid),
+            Engine::Lenia => self.lenia.step(),
+        }
+        if self.engine != Engine::Life {
+            self.changes.clear();
+        }
+        self.changes_revision = self.grid.revision();
+        self.generation += 1;
+        self.history.record(self.generation, self.grid.population());
+
+        // Periodicity only means something for deterministic, noise-free life
+        if self.engine == Engine::Life && self.rule.is_deterministic() && self.noise_rate <= 0.0 {
+            self.period.observe(&self.grid, self.generation);
+        } else {
+            self.period.reset();
+        }
+    }
+
+    /// Births and deaths of the last life generation, or none once the grid
+    /// has been edited since
+    pub fn changes(&self) -> &[Change] {
+        if self.grid.revision() == self.changes_revision {
+            &self.changes
+        } else {
+            &[]
+        }
+    }
+
+    /// Merge noise flips into the changes of the generation: a flip of a
+    /// cell the generation left alone is a change of its own, and a flip
+    /// of one it changed undoes that change
+    fn add_flips(&mut self, flips: Vec<Change>) {
+        if flips.is_empty() {
+            return;
+        }
+        let flipped: HashSet<(usize, usize)> = flips.iter().map(|f| (f.x, f.y)).collect();
+        let changed: HashSet<(usize, usize)> = self.changes.iter().map(|c| (c.x, c.y)).collect();
+        self.changes.retain(|c| !flipped.contains(&(c.x, c.y)));
+        self.changes
+            .extend(flips.into_iter().filter(|f| !changed.contains(&(f.x, f.y))));
+    }
+
+    /// Raise the noise rate by a decade, starting at 1e-4 and stopping at
+    /// 0.1; a higher rate from the command line is left as it is
+    pub fn noise_up(&mut self) {
+        self.noise_rate = if self.noise_rate <= 0.0 {
+            1e-4
+        } else {
+            (self.noise_rate * 10.0).min(self.noise_rate.max(0.1))
+        };
+    }
+
+    /// Lower the noise rate by a decade, switching it off below 1e-4
+    pub fn noise_down(&mut self) {
+        self.noise_rate = if self.noise_rate <= 1e-4 {
+            0.0
+        } else {
+            self.noise_rate / 10.0
+        };
+    }
+
+    /// Undo one generation; only invertible block rules can run backwards
+    pub fn step_back(&mut self) {
+        if self.engine == Engine::Margolus && self.margolus.step_back(&mut self.grid) {
+            self.generation = self.generation.saturating_sub(1);
+            self.history.record(self.generation, self.grid.population());
+        }
+    }
+
+    pub fn next_rule(&mut self) {
+        match self.engine {
+            Engine::Life | Engine::Lenia => {}
+            Engine::Elementary => self.elementary.next_rule(),
+            Engine::Turmite => self.turmites.next_rule(),
+            Engine::Margolus => self.margolus.next_rule(),
+        }
+    }
+
+    pub fn previous_rule(&mut self) {
+        match self.engine {
+            Engine::Life | Engine::Lenia => {}
+            Engine::Elementary => self.elementary.previous_rule(),
+            Engine::Turmite => self.turmites.previous_rule(),
+            Engine::Margolus => self.margolus.previous_rule(),
+        }
+    }
+
+    /// Set the rule of the current engine from its usual notation: B/S for
+    /// life, a Wolfram number for elementary, turns for turmites, a preset
+    /// or block table for margolus
+    pub fn set_rule(&mut self, rule: &str) -> Result<(), String> {
+        match self.engine {
+            Engine::Life => self.rule = rule.parse()?,
+            Engine::Elementary => {
+                self.elementary.rule = rule
+                    .parse()
+                    .map_err(|_| format!("not a Wolfram rule (0-255): '{rule}'"))?
+            }
+            Engine::Turmite => self.turmites.rule = rule.parse()?,
+            Engine::Margolus => self.margolus.rule = rule.parse()?,
+            Engine::Lenia => return Err(String::from("lenia has no rule to set")),
+        }
+        Ok(())
+    }
+
+    /// Seed at a grid position: a single top-row cell for the elementary
+    /// engine, an ant for the turmite engine, a random blob for Lenia
+    pub fn seed_single(&mut self, position: Coordinates) {
+        match self.engine {
+            Engine::Life | Engine::Margolus => {}
+            Engine::Elementary => {
+                self.elementary.seed_single(&mut self.grid, position.x);
+                self.generation = 0;
+            }
+            Engine::Turmite => self.turmites.place_ant(position.x, position.y),
+            Engine::Lenia => self.lenia.seed_blob(position.x, position.y, &mut self.rng),
+        }
+    }
+
+    /// Seed the simulation with random cells
+    pub fn seed_random(&mut self) {
+        match self.engine {
+            Engine::Life | Engine::Turmite | Engine::Margolus => {}
+            Engine::Elementary => {
+                self.elementary.seed_random(&mut self.grid, &mut self.rng);
+                self.generation = 0;
+            }
+            Engine::Lenia => {
+                self.lenia.seed_random(&mut self.rng);
+                self.generation = 0;
+            }
+        }
+    }
+}
+
+impl fmt::Display for Simulation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.engine {
+            Engine::Life => write!(f, "life {}", self.rule),
+            Engine::Elementary => write!(f, "elementary rule {}", self.elementary.rule),
+            Engine::Turmite => write!(
+                f,
+                "turmite {} ({} ants)",
+                self.turmites.rule,
+                self.turmites.ants.len()
+            ),
+            Engine::Margolus => write!(
+                f,
+                "margolus {} ({}{})",
+                self.margolus.rule,
+                self.margolus.phase_label(),
+                if self.margolus.rule.is_reversible() {
+                    ", reversible"
+                } else {
+                    ""
+                }
+            ),
+            Engine::Lenia => write!(
+                f,
+                "lenia R={} mu={} sigma={} dt={}",
+                self.lenia.params.radius,
+                self.lenia.params.mu,
+                self.lenia.params.sigma,
+                self.lenia.params.dt
+            ),
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use std::fmt;
+use std::str::FromStr;
 
 #[derive(Debug, Clone)]
 pub struct Size {
@@ -11,3 +12,16 @@ impl fmt::Display for Size {
         write!(f, "({}x{})", self.width, self.height)
     }
 }
+
+impl FromStr for Size {
+    type Err = String;
+
+    /// Parse `WIDTHxHEIGHT`, e.g. `256x256`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid size '{s}', expected WIDTHxHEIGHT");
+        let (width, height) = s.trim().split_once(['x', 'X']).ok_or_else(invalid)?;
+        let width = width.trim().parse().map_err(|_| invalid())?;
+        let height = height.trim().parse().map_err(|_| invalid())?;
+        Ok(Size { width, height })
+    }
+}
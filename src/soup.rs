@@ -0,0 +1,290 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::health::Health;
+use crate::health::Health::{Alive, Dead};
+use crate::rng::Rng;
+
+/// The symmetry group applied to a soup
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Group {
+    /// No symmetry
+    C1,
+    /// 180 degree rotation
+    C2,
+    /// 90 degree rotation
+    C4,
+    /// Mirror across a vertical axis
+    D2Orthogonal,
+    /// Mirror across the main diagonal
+    D2Diagonal,
+    /// Mirrors across both orthogonal axes
+    D4Orthogonal,
+    /// Mirrors across both diagonals
+    D4Diagonal,
+    /// All rotations and reflections
+    D8,
+}
+
+/// Required parity of a soup dimension: odd sizes put the symmetry axis
+/// through cell centres, even sizes between cells
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Parity {
+    Any,
+    Odd,
+    Even,
+}
+
+/// apgsearch symmetry names and what they mean here
+const SYMMETRIES: [(&str, Group, Parity, Parity); 16] = [
+    ("C1", Group::C1, Parity::Any, Parity::Any),
+    ("C2_1", Group::C2, Parity::Odd, Parity::Odd),
+    ("C2_2", Group::C2, Parity::Even, Parity::Odd),
+    ("C2_4", Group::C2, Parity::Even, Parity::Even),
+    ("C4_1", Group::C4, Parity::Odd, Parity::Odd),
+    ("C4_4", Group::C4, Parity::Even, Parity::Even),
+    ("D2_+1", Group::D2Orthogonal, Parity::Odd, Parity::Any),
+    ("D2_+2", Group::D2Orthogonal, Parity::Even, Parity::Any),
+    ("D2_x", Group::D2Diagonal, Parity::Any, Parity::Any),
+    ("D4_+1", Group::D4Orthogonal, Parity::Odd, Parity::Odd),
+    ("D4_+2", Group::D4Orthogonal, Parity::Even, Parity::Odd),
+    ("D4_+4", Group::D4Orthogonal, Parity::Even, Parity::Even),
+    ("D4_x1", Group::D4Diagonal, Parity::Odd, Parity::Odd),
+    ("D4_x4", Group::D4Diagonal, Parity::Even, Parity::Even),
+    ("D8_1", Group::D8, Parity::Odd, Parity::Odd),
+    ("D8_4", Group::D8, Parity::Even, Parity::Even),
+];
+
+/// A soup symmetry in apgsearch notation, e.g. `C1`, `C2_4`, `D4_+1`, `D8_1`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Symmetry {
+    index: usize,
+}
+
+impl Symmetry {
+    pub fn next(self) -> Self {
+        Self {
+            index: (self.index + 1) % SYMMETRIES.len(),
+        }
+    }
+
+    fn group(&self) -> Group {
+        SYMMETRIES[self.index].1
+    }
+
+    fn is_square(&self) -> bool {
+        matches!(
+            self.group(),
+            Group::C4 | Group::D2Diagonal | Group::D4Diagonal | Group::D8
+        )
+    }
+
+    /// Largest size not exceeding the requested one that fits the symmetry
+    pub fn fit(&self, width: usize, height: usize) -> (usize, usize) {
+        let adjust = |size: usize, parity: Parity| match parity {
+            Parity::Odd if size.is_multiple_of(2) => size.saturating_sub(1),
+            Parity::Even if !size.is_multiple_of(2) => size.saturating_sub(1),
+            _ => size,
+        };
+
+        let (_, _, x_parity, y_parity) = SYMMETRIES[self.index];
+        if self.is_square() {
+            let side = adjust(width.min(height), x_parity);
+            (side, side)
+        } else {
+            (adjust(width, x_parity), adjust(height, y_parity))
+        }
+    }
+
+    /// Images of a cell under every element of the symmetry group
+    fn orbit(&self, x: usize, y: usize, width: usize, height: usize) -> Vec<(usize, usize)> {
+        let (mx, my) = (width - 1 - x, height - 1 - y);
+        match self.group() {
+            Group::C1 => vec![(x, y)],
+            Group::C2 => vec![(x, y), (mx, my)],
+            Group::C4 => vec![(x, y), (my, x), (mx, my), (y, mx)],
+            Group::D2Orthogonal => vec![(x, y), (mx, y)],
+            Group::D2Diagonal => vec![(x, y), (y, x)],
+            Group::D4Orthogonal => vec![(x, y), (mx, y), (x, my), (mx, my)],
+            Group::D4Diagonal => vec![(x, y), (y, x), (my, mx), (mx, my)],
+            Group::D8 => vec![
+                (x, y),
+                (my, x),
+                (mx, my),
+                (y, mx),
+                (mx, y),
+                (x, my),
+                (y, x),
+                (my, mx),
+            ],
+        }
+    }
+}
+
+impl FromStr for Symmetry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SYMMETRIES
+            .iter()
+            .position(|(name, ..)| name.eq_ignore_ascii_case(s))
+            .map(|index| Self { index })
+            .ok_or_else(|| {
+                let names: Vec<&str> = SYMMETRIES.iter().map(|(name, ..)| *name).collect();
+                format!(
+                    "unknown symmetry '{s}', expected one of {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(SYMMETRIES[self.index].0)
+    }
+}
+
+/// Everything needed to regenerate a soup exactly
+#[derive(Clone, Debug)]
+pub struct Soup {
+    pub symmetry: Symmetry,
+    pub density: f64,
+    pub seed: u64,
+}
+
+impl Soup {
+    /// Generate the soup cells for a region, fitted to the symmetry's size
+    /// and parity constraints
+    pub fn generate(&self, width: usize, height: usize) -> Vec<Vec<Health>> {
+        let (width, height) = self.symmetry.fit(width, height);
+        let mut rng = Rng::new(self.seed);
+        let mut cells = vec![vec![Dead; width]; height];
+
+        // Cells are visited row by row, so the smallest member of an orbit is
+        // always drawn before the others copy it
+        for y in 0..height {
+            for x in 0..width {
+                let representative = self
+                    .symmetry
+                    .orbit(x, y, width, height)
+                    .into_iter()
+                    .min_by_key(|(ox, oy)| (*oy, *ox))
+                    .unwrap_or((x, y));
+
+                cells[y][x] = if representative == (x, y) {
+                    if rng.chance(self.density) {
+                        Alive
+                    } else {
+                        Dead
+                    }
+                } else {
+                    cells[representative.1][representative.0]
+                };
+            }
+        }
+
+        cells
+    }
+}
+
+impl fmt::Display for Soup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} density {} seed {}",
+            self.symmetry, self.density, self.seed
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Map = fn(usize, usize, usize, usize) -> (usize, usize);
+
+    const ROTATE_180: Map = |x, y, w, h| (w - 1 - x, h - 1 - y);
+    const ROTATE_90: Map = |x, y, _, h| (h - 1 - y, x);
+    const MIRROR_X: Map = |x, y, w, _| (w - 1 - x, y);
+    const MIRROR_Y: Map = |x, y, _, h| (x, h - 1 - y);
+    const TRANSPOSE: Map = |x, y, _, _| (y, x);
+    const ANTI_TRANSPOSE: Map = |x, y, w, h| (h - 1 - y, w - 1 - x);
+
+    /// Maps that generate the group, written out separately from `orbit`
+    fn generators(group: Group) -> Vec<Map> {
+        match group {
+            Group::C1 => vec![],
+            Group::C2 => vec![ROTATE_180],
+            Group::C4 => vec![ROTATE_90],
+            Group::D2Orthogonal => vec![MIRROR_X],
+            Group::D2Diagonal => vec![TRANSPOSE],
+            Group::D4Orthogonal => vec![MIRROR_X, MIRROR_Y],
+            Group::D4Diagonal => vec![TRANSPOSE, ANTI_TRANSPOSE],
+            Group::D8 => vec![ROTATE_90, MIRROR_X],
+        }
+    }
+
+    #[test]
+    fn soups_are_invariant_under_their_group() {
+        for (index, (name, group, ..)) in SYMMETRIES.iter().enumerate() {
+            for (width, height) in [(16, 16), (15, 15), (12, 9), (9, 12)] {
+                let soup = Soup {
+                    symmetry: Symmetry { index },
+                    density: 0.5,
+                    seed: 11,
+                };
+                let cells = soup.generate(width, height);
+                let (w, h) = (cells[0].len(), cells.len());
+                assert_eq!((w, h), soup.symmetry.fit(width, height), "{name}");
+                assert!(cells.iter().flatten().any(|c| *c == Alive), "{}", name);
+                assert!(cells.iter().flatten().any(|c| *c == Dead), "{}", name);
+
+                for map in generators(*group) {
+                    for y in 0..h {
+                        for x in 0..w {
+                            let (mx, my) = map(x, y, w, h);
+                            assert!(
+                                cells[y][x] == cells[my][mx],
+                                "{} {}x{} at ({}, {})",
+                                name,
+                                w,
+                                h,
+                                x,
+                                y
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sizes_are_fitted_to_the_parity() {
+        let fit = |name: &str, width, height| name.parse::<Symmetry>().unwrap().fit(width, height);
+        assert_eq!(fit("C1", 15, 16), (15, 16));
+        assert_eq!(fit("C2_1", 16, 16), (15, 15));
+        assert_eq!(fit("C2_2", 15, 16), (14, 15));
+        assert_eq!(fit("C2_4", 15, 15), (14, 14));
+        assert_eq!(fit("D2_+1", 16, 10), (15, 10));
+        assert_eq!(fit("D2_+2", 15, 9), (14, 9));
+        assert_eq!(fit("C4_1", 20, 16), (15, 15));
+        assert_eq!(fit("D8_4", 15, 21), (14, 14));
+        assert_eq!(fit("D2_x", 12, 9), (9, 9));
+
+        // Nothing is left of sizes that cannot hold the parity
+        for (name, ..) in SYMMETRIES {
+            assert_eq!(fit(name, 0, 0), (0, 0), "{name}");
+        }
+        assert_eq!(fit("C2_4", 1, 1), (0, 0));
+        assert_eq!(fit("D4_+2", 1, 1), (0, 1));
+        assert_eq!(fit("C2_1", 1, 1), (1, 1));
+        let soup = Soup {
+            symmetry: "C2_4".parse().unwrap(),
+            density: 1.0,
+            seed: 0,
+        };
+        assert!(soup.generate(1, 1).is_empty());
+    }
+}
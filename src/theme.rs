@@ -6,9 +6,40 @@ pub struct Theme {
     pub cell_alive: Style,
     pub cell_dead: Style,
     pub cursor: Style,
+    pub selection: Style,
     pub border: Style,
     pub grid_boundary: Style,
     pub gallery_focus: Style,
+    /// Letters of gallery names matched by the search
+    pub search_match: Style,
+    /// Bounding boxes and names of recognised objects
+    pub label_box: Style,
+    pub label_text: Style,
+    /// Colours for turmite cell states 1..n (state 0 is `cell_dead`)
+    pub turmite_palette: Vec<Style>,
+    pub ant: Style,
+    /// Gradient for continuous cell values, from low to high
+    pub lenia_gradient: Vec<Style>,
+    /// Live cell colours from newborn to long-lived
+    pub age_gradient: Vec<Style>,
+    /// Dead cell colours from just died to nearly faded
+    pub trail_gradient: Vec<Style>,
+    /// Cell colours from rarely to most often alive
+    pub heat_gradient: Vec<Style>,
+    /// Cells born and cells that died in the last generation
+    pub born: Style,
+    pub died: Style,
+    /// Census entries named from the gallery, and unstable ones
+    pub census_named: Style,
+    pub census_unstable: Style,
+    /// Breakpoint that paused the run, and disabled breakpoints
+    pub breakpoint_fired: Style,
+    pub breakpoint_disabled: Style,
+    /// Population chart series and its min, max and current markers
+    pub chart_line: Style,
+    pub chart_min: Style,
+    pub chart_max: Style,
+    pub chart_current: Style,
 }
 
 impl Default for Theme {
@@ -19,11 +50,128 @@ impl Default for Theme {
             cell_alive: Style::default().fg(Color::Green),
             cell_dead: Style::default(),
             cursor: Style::default().bg(Color::DarkGray),
+            selection: Style::default().bg(Color::Indexed(236)),
             border: Style::default().fg(Color::Gray),
             grid_boundary: Style::default().fg(Color::DarkGray),
             gallery_focus: Style::default()
                 .bg(Color::DarkGray)
                 .add_modifier(Modifier::BOLD),
+            search_match: Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            label_box: Style::default().fg(Color::Yellow),
+            label_text: Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            turmite_palette: vec![
+                Style::default().fg(Color::Green),
+                Style::default().fg(Color::Yellow),
+                Style::default().fg(Color::Blue),
+                Style::default().fg(Color::Magenta),
+                Style::default().fg(Color::Cyan),
+                Style::default().fg(Color::LightRed),
+                Style::default().fg(Color::White),
+            ],
+            ant: Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            lenia_gradient: vec![
+                Style::default().fg(Color::Blue),
+                Style::default().fg(Color::Cyan),
+                Style::default().fg(Color::Green),
+                Style::default().fg(Color::Yellow),
+                Style::default().fg(Color::Red),
+            ],
+            age_gradient: vec![
+                Style::default().fg(Color::White),
+                Style::default().fg(Color::LightYellow),
+                Style::default().fg(Color::Yellow),
+                Style::default().fg(Color::LightGreen),
+                Style::default().fg(Color::Green),
+                Style::default().fg(Color::Cyan),
+                Style::default().fg(Color::Blue),
+            ],
+            trail_gradient: vec![
+                Style::default().fg(Color::LightRed),
+                Style::default().fg(Color::Red),
+                Style::default().fg(Color::Magenta),
+                Style::default().fg(Color::DarkGray),
+            ],
+            heat_gradient: vec![
+                Style::default().fg(Color::Blue),
+                Style::default().fg(Color::Magenta),
+                Style::default().fg(Color::Red),
+                Style::default().fg(Color::LightRed),
+                Style::default().fg(Color::Yellow),
+                Style::default().fg(Color::White),
+            ],
+            born: Style::default().fg(Color::LightGreen),
+            died: Style::default().fg(Color::LightRed),
+            census_named: Style::default().fg(Color::Yellow),
+            census_unstable: Style::default().fg(Color::DarkGray),
+            breakpoint_fired: Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            breakpoint_disabled: Style::default().fg(Color::DarkGray),
+            chart_line: Style::default().fg(Color::Green),
+            chart_min: Style::default().fg(Color::Cyan),
+            chart_max: Style::default().fg(Color::Magenta),
+            chart_current: Style::default().fg(Color::Yellow),
+        }
+    }
+}
+
+/// Names accepted by `Theme::named`; `dark` is the default
+pub const THEMES: [&str; 2] = ["dark", "light"];
+
+impl Theme {
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::default()),
+            "light" => Some(Self::light()),
+            _ => None,
+        }
+    }
+
+    /// Colours readable on a light terminal background
+    fn light() -> Self {
+        Self {
+            header_style: Style::default()
+                .fg(Color::Blue)
+                .add_modifier(Modifier::BOLD),
+            footer_style: Style::default()
+                .fg(Color::Blue)
+                .add_modifier(Modifier::BOLD),
+            cell_alive: Style::default().fg(Color::Black),
+            cursor: Style::default().bg(Color::Indexed(250)),
+            selection: Style::default().bg(Color::Indexed(254)),
+            border: Style::default().fg(Color::DarkGray),
+            grid_boundary: Style::default().fg(Color::Gray),
+            gallery_focus: Style::default()
+                .bg(Color::Indexed(252))
+                .add_modifier(Modifier::BOLD),
+            search_match: Style::default()
+                .fg(Color::Blue)
+                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            label_box: Style::default().fg(Color::Magenta),
+            label_text: Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+            age_gradient: vec![
+                Style::default().fg(Color::Red),
+                Style::default().fg(Color::Magenta),
+                Style::default().fg(Color::Blue),
+                Style::default().fg(Color::Black),
+            ],
+            heat_gradient: vec![
+                Style::default().fg(Color::Blue),
+                Style::default().fg(Color::Magenta),
+                Style::default().fg(Color::Red),
+                Style::default().fg(Color::Black),
+            ],
+            born: Style::default().fg(Color::Green),
+            died: Style::default().fg(Color::Red),
+            census_named: Style::default().fg(Color::Magenta),
+            census_unstable: Style::default().fg(Color::Gray),
+            chart_line: Style::default().fg(Color::Black),
+            chart_current: Style::default().fg(Color::Red),
+            ..Self::default()
         }
     }
 }
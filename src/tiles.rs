@@ -0,0 +1,87 @@
+use std::ops::Range;
+
+use crate::size::Size;
+
+/// Side of a square tile in cells
+pub const TILE_SIZE: usize = 32;
+
+/// Which tiles of the grid need recomputing. A tile is active for a
+/// generation when it, or one of its eight neighbours, changed since the
+/// previous generation; every other tile is dead or static and is skipped.
+#[derive(Clone)]
+pub struct Tiles {
+    columns: usize,
+    rows: usize,
+    /// Tiles with a cell that changed since the last generation
+    dirty: Vec<bool>,
+    /// Tiles recomputed in the current (or last) generation
+    active: Vec<bool>,
+    active_count: usize,
+}
+
+impl Tiles {
+    pub fn new(size: &Size) -> Self {
+        let columns = size.width.div_ceil(TILE_SIZE);
+        let rows = size.height.div_ceil(TILE_SIZE);
+        Self {
+            columns,
+            rows,
+            dirty: vec![false; columns * rows],
+            active: vec![false; columns * rows],
+            active_count: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.columns * self.rows
+    }
+
+    pub fn active_count(&self) -> usize {
+        self.active_count
+    }
+
+    /// Note that the cell at (x, y) changed
+    pub fn mark(&mut self, x: usize, y: usize) {
+        self.dirty[(y / TILE_SIZE) * self.columns + x / TILE_SIZE] = true;
+    }
+
+    /// Recompute every tile next generation
+    pub fn mark_all(&mut self) {
+        self.dirty.fill(true);
+    }
+
+    /// Choose the tiles to recompute from the dirty ones and start
+    /// collecting changes for the next generation
+    pub fn activate(&mut self) {
+        self.active.fill(false);
+        for ty in 0..self.rows {
+            for tx in 0..self.columns {
+                if !self.dirty[ty * self.columns + tx] {
+                    continue;
+                }
+                for ny in ty.saturating_sub(1)..(ty + 2).min(self.rows) {
+                    for nx in tx.saturating_sub(1)..(tx + 2).min(self.columns) {
+                        self.active[ny * self.columns + nx] = true;
+                    }
+                }
+            }
+        }
+        self.dirty.fill(false);
+        self.active_count = self.active.iter().filter(|active| **active).count();
+    }
+
+    /// Column ranges of the active tiles crossing row `y`, left to right
+    pub fn active_spans(&self, y: usize, width: usize) -> impl Iterator<Item = Range<usize>> + '_ {
+        let ty = y / TILE_SIZE;
+        (0..self.columns)
+            .filter(move |tx| self.active[ty * self.columns + tx])
+            .map(move |tx| tx * TILE_SIZE..((tx + 1) * TILE_SIZE).min(width))
+    }
+
+    /// Whether any active tile crosses row `y` within the columns `xs`
+    pub fn is_span_active(&self, y: usize, xs: Range<usize>) -> bool {
+        let ty = y / TILE_SIZE;
+        let last = (xs.end.saturating_sub(1) / TILE_SIZE).min(self.columns.saturating_sub(1));
+        (xs.start / TILE_SIZE..=last).any(|tx| self.active[ty * self.columns + tx])
+    }
+}
@@ -0,0 +1,224 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::coordinates::Coordinates;
+use crate::grid::Grid;
+use crate::health::Health::Alive;
+
+/// Rule presets cycled with the previous/next rule commands
+const PRESETS: [&str; 6] = [
+    "RL",
+    "RLR",
+    "LLRR",
+    "LRRRRRLLR",
+    "LLRRRLRLRLLR",
+    "RRLLLRLLLRRR",
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Turn {
+    Left,
+    Right,
+    Straight,
+    Reverse,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Heading {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Heading {
+    fn turn(self, turn: Turn) -> Self {
+        let index = self as usize;
+        let offset = match turn {
+            Turn::Straight => 0,
+            Turn::Right => 1,
+            Turn::Reverse => 2,
+            Turn::Left => 3,
+        };
+        [Heading::North, Heading::East, Heading::South, Heading::West][(index + offset) % 4]
+    }
+}
+
+/// A turmite rule such as `RL` (Langton's ant) or `LLRR`.
+///
+/// Each character is the turn taken on a cell of that colour; the cell then
+/// advances to the next colour.
+#[derive(Clone, Debug)]
+pub struct TurmiteRule {
+    turns: Vec<Turn>,
+}
+
+impl FromStr for TurmiteRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let turns = s
+            .chars()
+            .map(|c| match c.to_ascii_uppercase() {
+                'L' => Ok(Turn::Left),
+                'R' => Ok(Turn::Right),
+                'N' => Ok(Turn::Straight),
+                'U' => Ok(Turn::Reverse),
+                other => Err(format!("invalid turn '{other}', expected L, R, N or U")),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if turns.len() < 2 || turns.len() > u8::MAX as usize {
+            return Err(format!(
+                "turmite rule needs between 2 and {} turns",
+                u8::MAX
+            ));
+        }
+
+        Ok(Self { turns })
+    }
+}
+
+impl fmt::Display for TurmiteRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for turn in &self.turns {
+            let c = match turn {
+                Turn::Left => 'L',
+                Turn::Right => 'R',
+                Turn::Straight => 'N',
+                Turn::Reverse => 'U',
+            };
+            write!(f, "{c}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct Ant {
+    pub x: usize,
+    pub y: usize,
+    pub heading: Heading,
+}
+
+/// Turmite engine: ants walking over the grid, recolouring cells as they go.
+///
+/// Colours are kept in a layer alongside the grid; colour 0 is a dead cell and
+/// any other colour is alive, so the grid stays meaningful to the other engines.
+#[derive(Clone)]
+pub struct Turmites {
+    pub rule: TurmiteRule,
+    pub ants: Vec<Ant>,
+    colors: Vec<Vec<u8>>,
+}
+
+impl Turmites {
+    pub fn new(rule: TurmiteRule) -> Self {
+        Self {
+            rule,
+            ants: Vec::new(),
+            colors: Vec::new(),
+        }
+    }
+
+    /// Colour of a cell, reconciled with the grid so manual edits are respected
+    pub fn color(&self, grid: &Grid, x: usize, y: usize) -> u8 {
+        match grid.line(y).and_then(|line| line.get(x)) {
+            Some(Alive) => self
+                .colors
+                .get(y)
+                .and_then(|row| row.get(x))
+                .copied()
+                .unwrap_or(0)
+                .max(1),
+            _ => 0,
+        }
+    }
+
+    pub fn ant_at(&self, x: usize, y: usize) -> Option<&Ant> {
+        self.ants.iter().find(|ant| ant.x == x && ant.y == y)
+    }
+
+    /// Place an ant heading north, or turn an existing ant clockwise
+    pub fn place_ant(&mut self, x: usize, y: usize) {
+        if let Some(ant) = self.ants.iter_mut().find(|ant| ant.x == x && ant.y == y) {
+            ant.heading = ant.heading.turn(Turn::Right);
+        } else {
+            self.ants.push(Ant {
+                x,
+                y,
+                heading: Heading::North,
+            });
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.ants.clear();
+        self.colors.clear();
+    }
+
+    pub fn next_rule(&mut self) {
+        self.cycle_preset(true);
+    }
+
+    pub fn previous_rule(&mut self) {
+        self.cycle_preset(false);
+    }
+
+    /// Move to the neighbouring preset. A custom rule counts as sitting just
+    /// before the first preset and just after the last.
+    fn cycle_preset(&mut self, forward: bool) {
+        let current = self.rule.to_string();
+        let last = PRESETS.len() - 1;
+        let index = match (
+            PRESETS.iter().position(|preset| *preset == current),
+            forward,
+        ) {
+            (Some(i), true) => (i + 1) % PRESETS.len(),
+            (Some(i), false) => (i + last) % PRESETS.len(),
+            (None, true) => 0,
+            (None, false) => last,
+        };
+        if let Ok(rule) = PRESETS[index].parse() {
+            self.rule = rule;
+        }
+    }
+
+    pub fn step(&mut self, grid: &mut Grid) {
+        let size = grid.get_size().clone();
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+        if self.colors.len() != size.height || self.colors[0].len() != size.width {
+            self.colors = vec![vec![0; size.width]; size.height];
+        }
+
+        let states = self.rule.turns.len() as u8;
+        for i in 0..self.ants.len() {
+            let (x, y) = (self.ants[i].x, self.ants[i].y);
+            if x >= size.width || y >= size.height {
+                continue;
+            }
+
+            let color = self.color(grid, x, y) % states;
+            let next = (color + 1) % states;
+            self.colors[y][x] = next;
+            let position = Coordinates { x, y };
+            if next == 0 {
+                grid.kill(position);
+            } else {
+                grid.resurrect(position);
+            }
+
+            // Turn, then move forward one cell, wrapping at the grid edges
+            let ant = &mut self.ants[i];
+            ant.heading = ant.heading.turn(self.rule.turns[color as usize]);
+            match ant.heading {
+                Heading::North => ant.y = (y + size.height - 1) % size.height,
+                Heading::South => ant.y = (y + 1) % size.height,
+                Heading::East => ant.x = (x + 1) % size.width,
+                Heading::West => ant.x = (x + size.width - 1) % size.width,
+            }
+        }
+    }
+}
@@ -65,7 +65,6 @@ impl Viewport {
     }
 
     /// Pan the viewport left
-    #[allow(dead_code)]
     pub fn pan_left(&mut self, amount: usize, _grid_width: usize) {
         if self.x_offset >= amount {
             self.x_offset -= amount;
@@ -75,7 +74,6 @@ impl Viewport {
     }
 
     /// Pan the viewport right
-    #[allow(dead_code)]
     pub fn pan_right(&mut self, amount: usize, grid_width: usize) {
         let max_offset = grid_width.saturating_sub(self.viewport_size.width);
         let new_offset = self.x_offset + amount;
@@ -87,7 +85,6 @@ impl Viewport {
     }
 
     /// Pan the viewport up
-    #[allow(dead_code)]
     pub fn pan_up(&mut self, amount: usize, _grid_height: usize) {
         if self.y_offset >= amount {
             self.y_offset -= amount;
@@ -97,7 +94,6 @@ impl Viewport {
     }
 
     /// Pan the viewport down
-    #[allow(dead_code)]
     pub fn pan_down(&mut self, amount: usize, grid_height: usize) {
         let max_offset = grid_height.saturating_sub(self.viewport_size.height);
         let new_offset = self.y_offset + amount;
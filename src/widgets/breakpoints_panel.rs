@@ -0,0 +1,53 @@
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, List, ListItem};
+
+use crate::app::{App, AppMode};
+use crate::theme::Theme;
+
+/// Side panel listing the breakpoints that pause the run loop
+pub struct BreakpointsPanel<'a> {
+    app: &'a App,
+    theme: &'a Theme,
+}
+
+impl<'a> BreakpointsPanel<'a> {
+    pub fn new(app: &'a App, theme: &'a Theme) -> Self {
+        Self { app, theme }
+    }
+}
+
+impl Widget for BreakpointsPanel<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let breakpoints = &self.app.breakpoints;
+        let focused = matches!(self.app.mode, AppMode::Breakpoints | AppMode::Prompt);
+
+        let items: Vec<ListItem> = breakpoints
+            .list
+            .iter()
+            .enumerate()
+            .map(|(i, breakpoint)| {
+                let label = breakpoint.condition.to_string();
+                let mut style = Style::default();
+                if !breakpoint.enabled {
+                    style = style.patch(self.theme.breakpoint_disabled);
+                } else if breakpoints.fired == Some(breakpoint.id) {
+                    style = style.patch(self.theme.breakpoint_fired);
+                }
+                if focused && i == breakpoints.selected {
+                    style = style.patch(self.theme.gallery_focus);
+                }
+                let check = if breakpoint.enabled { "x" } else { " " };
+                ListItem::new(Line::from(format!("[{check}] {label}")).style(style))
+            })
+            .collect();
+
+        let list = List::new(items).block(
+            Block::bordered()
+                .title(format!(" Breakpoints ({}) ", breakpoints.list.len()))
+                .title_bottom(" >/</n/r/p add ")
+                .border_style(self.theme.border),
+        );
+
+        Widget::render(list, area, buf);
+    }
+}
@@ -0,0 +1,50 @@
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, List, ListItem};
+
+use crate::app::App;
+use crate::census::ObjectKind;
+use crate::theme::Theme;
+
+/// Side panel listing the objects found by the last census
+pub struct CensusPanel<'a> {
+    app: &'a App,
+    theme: &'a Theme,
+}
+
+impl<'a> CensusPanel<'a> {
+    pub fn new(app: &'a App, theme: &'a Theme) -> Self {
+        Self { app, theme }
+    }
+}
+
+impl Widget for CensusPanel<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let census = &self.app.census;
+
+        let items: Vec<ListItem> = census
+            .entries
+            .iter()
+            .map(|entry| {
+                let mut style = Style::default();
+                if entry.name.is_some() {
+                    style = style.patch(self.theme.census_named);
+                } else if entry.classification.kind == ObjectKind::Unstable {
+                    style = style.patch(self.theme.census_unstable);
+                }
+                ListItem::new(vec![
+                    Line::from(format!("{:>4} {}", entry.count, entry.label())).style(style),
+                    Line::from(format!("     {}", entry.classification.kind))
+                        .style(self.theme.grid_boundary),
+                ])
+            })
+            .collect();
+
+        let list = List::new(items).block(
+            Block::bordered()
+                .title(format!(" Census ({}) ", census.total))
+                .border_style(self.theme.border),
+        );
+
+        Widget::render(list, area, buf);
+    }
+}
@@ -1,9 +1,12 @@
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Paragraph};
 
-use crate::app::App;
+use crate::app::{App, PromptAction};
 use crate::theme::Theme;
 
+/// Characters in the fast-forward progress bar
+const PROGRESS_WIDTH: u64 = 20;
+
 pub struct FooterBar<'a> {
     app: &'a App,
     theme: &'a Theme,
@@ -17,7 +20,7 @@ impl<'a> FooterBar<'a> {
 
 impl Widget for FooterBar<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let grid_size = self.app.grid.get_size();
+        let grid_size = self.app.simulation.grid.get_size();
         let cursor_grid = self.app.grid_cursor();
         let running_label = if self.app.running {
             "running"
@@ -39,19 +42,114 @@ impl Widget for FooterBar<'_> {
             ("none", 0)
         };
 
-        let footer = format!(
-            "grid {}, viewport {}, cursor {}, {}, pattern: {}, last: {}, rotation: {}°",
+        let selection = match self.app.selection() {
+            Some((_, size)) => format!(", selection {size}"),
+            None => String::new(),
+        };
+
+        let status = format!(
+            "grid {}, viewport {}, cursor {}{}, pattern: {}, last: {}, rotation: {}°, soup: {}",
             grid_size,
             self.app.viewport_size,
             cursor_grid,
-            running_label,
+            selection,
             self.app.current_pattern_type_name(),
             last_pattern,
-            rotation_angle
+            rotation_angle,
+            self.app.soup
         );
 
+        let stability = match self.app.simulation.period.stability() {
+            Some(stability) => stability.to_string(),
+            None => String::from("no"),
+        };
+        let auto_pause = if self.app.pause_when_stable {
+            " (auto-pause)"
+        } else {
+            ""
+        };
+        let fired = match self.app.breakpoints.fired() {
+            Some(fired) => format!(", break: {}", fired.condition),
+            None => String::new(),
+        };
+
+        let (active_tiles, tiles) = self.app.simulation.grid.active_tiles();
+        let simulation = format!(
+            "{}, {}, view: {}, gen: {}, tiles: {}/{}, delay: {}ms x{}, {:.0} gen/s, {:.0} fps, noise: {}, seed: {}, stable: {}{}{}",
+            running_label,
+            self.app.simulation,
+            self.app.display_mode,
+            self.app.simulation.generation,
+            active_tiles,
+            tiles,
+            self.app.simulation_delay,
+            self.app.gens_per_frame,
+            self.app.gens_per_second,
+            self.app.frames_per_second,
+            self.app.simulation.noise_rate,
+            self.app.simulation.seed,
+            stability,
+            auto_pause,
+            fired
+        );
+
+        let footer = vec![Line::from(status), Line::from(simulation)];
+
+        let mut block = Block::bordered();
+        if let Some(jump) = &self.app.fast_forward {
+            let total = jump.target.saturating_sub(jump.start).max(1);
+            let done = jump.generation.saturating_sub(jump.start);
+            let filled = (done * PROGRESS_WIDTH / total).min(PROGRESS_WIDTH) as usize;
+            block = block.title(format!(
+                " jumping to gen {} [{}{}] {}% in {:.1}s, esc to cancel ",
+                jump.target,
+                "#".repeat(filled),
+                "-".repeat(PROGRESS_WIDTH as usize - filled),
+                done * 100 / total,
+                jump.started.elapsed().as_secs_f64()
+            ));
+        } else if let Some(prompt) = &self.app.prompt {
+            let separator = if matches!(
+                prompt.action,
+                PromptAction::Command | PromptAction::GallerySearch
+            ) {
+                ""
+            } else {
+                " "
+            };
+            block = block.title(format!(
+                " {}{}{}█ ",
+                prompt.action.label(),
+                separator,
+                prompt.input
+            ));
+            if prompt.completions.len() > 1 {
+                let candidates: Vec<String> = prompt
+                    .completions
+                    .iter()
+                    .enumerate()
+                    .map(|(index, line)| {
+                        let word = line.trim_end().rsplit(' ').next().unwrap_or(line);
+                        if index == prompt.completion {
+                            format!("[{word}]")
+                        } else {
+                            word.to_string()
+                        }
+                    })
+                    .collect();
+                block =
+                    block.title(Line::from(format!(" {} ", candidates.join(" "))).right_aligned());
+            }
+        } else if let Some(message) = &self.app.message {
+            block = block.title(format!(" {message} "));
+        }
+
+        if !self.app.pending_keys.is_empty() {
+            block = block.title(Line::from(format!(" {} ", self.app.pending_keys)).right_aligned());
+        }
+
         let widget = Paragraph::new(footer)
-            .block(Block::bordered())
+            .block(block)
             .style(self.theme.footer_style);
 
         widget.render(area, buf);
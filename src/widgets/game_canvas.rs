@@ -1,17 +1,33 @@
+use std::collections::HashMap;
+
 use ratatui::prelude::*;
 
-use crate::app::App;
+use crate::app::{App, DisplayMode};
 use crate::health::Health;
+use crate::simulation::Engine;
 use crate::theme::Theme;
+use crate::turmite::Heading;
 
 const DEAD_SYMBOL: &str = " ";
 const ALIVE_SYMBOL: &str = "🚀";
+const TURMITE_SYMBOL: &str = "█";
+const DENSITY_SYMBOLS: [&str; 5] = [" ", "░", "▒", "▓", "█"];
+const SHADED_SYMBOL: &str = "█";
+const TRAIL_SYMBOL: &str = "░";
+const DIED_SYMBOL: &str = "▒";
+/// Generations a dead cell stays visible in the trails mode
+const TRAIL_LENGTH: u32 = 16;
 
 // Box-drawing characters for grid boundary
 const BOUNDARY_VERTICAL: &str = "│";
 const BOUNDARY_HORIZONTAL: &str = "─";
 const BOUNDARY_CORNER: &str = "┘";
 
+// Box-drawing characters for object labels
+const LABEL_HORIZONTAL: &str = "─";
+const LABEL_VERTICAL: &str = "│";
+const LABEL_CORNERS: [&str; 4] = ["┌", "┐", "└", "┘"];
+
 pub struct GameCanvas<'a> {
     app: &'a App,
     theme: &'a Theme,
@@ -21,15 +37,184 @@ impl<'a> GameCanvas<'a> {
     pub fn new(app: &'a App, theme: &'a Theme) -> Self {
         Self { app, theme }
     }
+
+    /// Shade continuous values with density glyphs and the theme gradient
+    fn lenia_cell(&self, x: usize, y: usize, style: Style) -> (&'static str, Style) {
+        let value = self.app.simulation.lenia.value(x, y).clamp(0.0, 1.0);
+        let glyph = (value * (DENSITY_SYMBOLS.len() - 1) as f32).round() as usize;
+        let gradient = &self.theme.lenia_gradient;
+        if glyph == 0 || gradient.is_empty() {
+            return (DENSITY_SYMBOLS[0], style);
+        }
+        let shade = ((value * gradient.len() as f32) as usize).min(gradient.len() - 1);
+        (DENSITY_SYMBOLS[glyph], gradient[shade])
+    }
+
+    /// Colour life cells by age, trail or heat depending on the display mode
+    fn life_cell(
+        &self,
+        x: usize,
+        y: usize,
+        health: Health,
+        max_activity: u32,
+        symbol: &'static str,
+        style: Style,
+    ) -> (&'static str, Style) {
+        let grid = &self.app.simulation.grid;
+        match (self.app.display_mode, health) {
+            (DisplayMode::Age, Health::Alive) if !self.theme.age_gradient.is_empty() => {
+                let gradient = &self.theme.age_gradient;
+                // Doubling age buckets: 1, 2, 3-4, 5-8, ...
+                let bucket = (grid.age(x, y).saturating_add(1)).ilog2() as usize;
+                (SHADED_SYMBOL, gradient[bucket.min(gradient.len() - 1)])
+            }
+            (DisplayMode::Trails, Health::Dead) if !self.theme.trail_gradient.is_empty() => {
+                let age = grid.age(x, y);
+                if age >= TRAIL_LENGTH {
+                    return (symbol, style);
+                }
+                let gradient = &self.theme.trail_gradient;
+                let shade = age as usize * gradient.len() / TRAIL_LENGTH as usize;
+                (TRAIL_SYMBOL, gradient[shade])
+            }
+            (DisplayMode::Heatmap, Health::Dead) if !self.theme.heat_gradient.is_empty() => {
+                let activity = grid.activity(x, y);
+                if activity == 0 || max_activity == 0 {
+                    return (symbol, style);
+                }
+                let heat = activity as f32 / max_activity as f32;
+                let gradient = &self.theme.heat_gradient;
+                let glyph = 1 + (heat * (DENSITY_SYMBOLS.len() - 2) as f32).round() as usize;
+                let shade = ((heat * gradient.len() as f32) as usize).min(gradient.len() - 1);
+                (DENSITY_SYMBOLS[glyph], gradient[shade])
+            }
+            _ => (symbol, style),
+        }
+    }
+
+    /// Colour turmite cells by state and draw ants as arrows
+    fn turmite_cell(
+        &self,
+        x: usize,
+        y: usize,
+        symbol: &'static str,
+        style: Style,
+    ) -> (&'static str, Style) {
+        let turmites = &self.app.simulation.turmites;
+
+        if let Some(ant) = turmites.ant_at(x, y) {
+            let arrow = match ant.heading {
+                Heading::North => "▲",
+                Heading::East => "▶",
+                Heading::South => "▼",
+                Heading::West => "◀",
+            };
+            return (arrow, self.theme.ant);
+        }
+
+        let color = turmites.color(&self.app.simulation.grid, x, y) as usize;
+        match color {
+            0 => (symbol, style),
+            _ if self.theme.turmite_palette.is_empty() => (symbol, style),
+            _ => (
+                TURMITE_SYMBOL,
+                self.theme.turmite_palette[(color - 1) % self.theme.turmite_palette.len()],
+            ),
+        }
+    }
+
+    /// Draw a box around each recognised object with its name on the top edge.
+    /// Only dead cells are drawn over, so neighbouring objects stay visible.
+    fn draw_labels(&self, area: Rect, buf: &mut Buffer, max_width: u16, max_height: u16) {
+        let grid = &self.app.simulation.grid;
+        let (x_offset, y_offset) = (self.app.viewport.x_offset(), self.app.viewport.y_offset());
+
+        let mut put = |grid_x: isize, grid_y: isize, symbol: &str, style: Style| {
+            if grid_x < x_offset as isize || grid_y < y_offset as isize {
+                return;
+            }
+            let (view_x, view_y) = (grid_x as usize - x_offset, grid_y as usize - y_offset);
+            if view_x >= max_width as usize || view_y >= max_height as usize {
+                return;
+            }
+            let position = crate::coordinates::Coordinates {
+                x: grid_x as usize,
+                y: grid_y as usize,
+            };
+            if grid.get_cell(position) != Some(Health::Dead) {
+                return;
+            }
+            let cell = &mut buf[(area.x + view_x as u16, area.y + view_y as u16)];
+            cell.set_symbol(symbol);
+            cell.set_style(style);
+        };
+
+        for label in &self.app.labels {
+            let left = label.position.x as isize - 1;
+            let top = label.position.y as isize - 1;
+            let right = (label.position.x + label.size.width) as isize;
+            let bottom = (label.position.y + label.size.height) as isize;
+
+            for x in left + 1..right {
+                put(x, top, LABEL_HORIZONTAL, self.theme.label_box);
+                put(x, bottom, LABEL_HORIZONTAL, self.theme.label_box);
+            }
+            for y in top + 1..bottom {
+                put(left, y, LABEL_VERTICAL, self.theme.label_box);
+                put(right, y, LABEL_VERTICAL, self.theme.label_box);
+            }
+            put(left, top, LABEL_CORNERS[0], self.theme.label_box);
+            put(right, top, LABEL_CORNERS[1], self.theme.label_box);
+            put(left, bottom, LABEL_CORNERS[2], self.theme.label_box);
+            put(right, bottom, LABEL_CORNERS[3], self.theme.label_box);
+
+            // Name along the top edge, or the bottom edge at the top of the grid
+            let name_y = if top >= 0 { top } else { bottom };
+            for (i, c) in label.name.chars().enumerate() {
+                let mut symbol = [0u8; 4];
+                put(
+                    left + 1 + i as isize,
+                    name_y,
+                    c.encode_utf8(&mut symbol),
+                    self.theme.label_text,
+                );
+            }
+        }
+    }
 }
 
 impl Widget for GameCanvas<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let grid_size = self.app.grid.get_size();
+        let simulation = &self.app.simulation;
+        let grid_size = simulation.grid.get_size();
+        let inverted =
+            simulation.engine == Engine::Margolus && simulation.margolus.inverted_display();
 
+        let selection = self.app.selection();
+        let changes: HashMap<(usize, usize), Health> = if self.app.display_mode == DisplayMode::Diff
+        {
+            simulation
+                .changes()
+                .iter()
+                .map(|change| ((change.x, change.y), change.health))
+                .collect()
+        } else {
+            HashMap::new()
+        };
         let max_height = area.height.min(self.app.viewport_size.height as u16);
         let max_width = area.width.min(self.app.viewport_size.width as u16);
 
+        // The heat scale is relative to the hottest visible cell
+        let max_activity = if self.app.display_mode == DisplayMode::Heatmap {
+            let (left, top) = (self.app.viewport.x_offset(), self.app.viewport.y_offset());
+            simulation.grid.max_activity(
+                left..left + max_width as usize,
+                top..top + max_height as usize,
+            )
+        } else {
+            0
+        };
+
         // Calculate where the grid ends in viewport coordinates
         let grid_end_x = grid_size.width.saturating_sub(self.app.viewport.x_offset());
         let grid_end_y = grid_size
@@ -52,28 +237,59 @@ impl Widget for GameCanvas<'_> {
                     x: grid_x,
                     y: grid_y,
                 };
-                let health = self.app.grid.get_cell(coord).unwrap_or(Health::Dead);
+                let mut health = simulation.grid.get_cell(coord).unwrap_or(Health::Dead);
+                if inverted {
+                    health = match health {
+                        Health::Alive => Health::Dead,
+                        Health::Dead => Health::Alive,
+                    };
+                }
 
-                let mut style = match health {
-                    Health::Alive => self.theme.cell_alive,
-                    Health::Dead => self.theme.cell_dead,
+                let (mut symbol, mut style) = match health {
+                    Health::Alive => (ALIVE_SYMBOL, self.theme.cell_alive),
+                    Health::Dead => (DEAD_SYMBOL, self.theme.cell_dead),
                 };
 
+                match simulation.engine {
+                    Engine::Life => match changes.get(&(grid_x, grid_y)) {
+                        Some(Health::Alive) => (symbol, style) = (SHADED_SYMBOL, self.theme.born),
+                        Some(Health::Dead) => (symbol, style) = (DIED_SYMBOL, self.theme.died),
+                        None => {
+                            (symbol, style) =
+                                self.life_cell(grid_x, grid_y, health, max_activity, symbol, style);
+                        }
+                    },
+                    Engine::Turmite => {
+                        (symbol, style) = self.turmite_cell(grid_x, grid_y, symbol, style);
+                    }
+                    Engine::Lenia => {
+                        (symbol, style) = self.lenia_cell(grid_x, grid_y, self.theme.cell_dead);
+                    }
+                    _ => {}
+                }
+
+                if let Some((corner, size)) = &selection {
+                    if (corner.x..corner.x + size.width).contains(&grid_x)
+                        && (corner.y..corner.y + size.height).contains(&grid_y)
+                    {
+                        style = style.patch(self.theme.selection);
+                    }
+                }
+
                 if self.app.cursor.x == x as usize && self.app.cursor.y == y as usize {
                     style = style.patch(self.theme.cursor);
                 }
 
-                let symbol = match health {
-                    Health::Alive => ALIVE_SYMBOL,
-                    Health::Dead => DEAD_SYMBOL,
-                };
-
                 let cell = &mut buf[(area.x + x, area.y + y)];
                 cell.set_symbol(symbol);
                 cell.set_style(style);
             }
         }
 
+        if simulation.engine == Engine::Life {
+            self.draw_labels(area, buf, max_width, max_height);
+        }
+
         // Draw grid boundary if viewport extends beyond grid
         let boundary_style = self.theme.grid_boundary;
 
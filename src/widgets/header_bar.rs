@@ -21,6 +21,8 @@ impl Widget for HeaderBar<'_> {
             AppMode::Normal => "Normal",
             AppMode::Help => "Help",
             AppMode::PatternGallery => "Pattern Gallery",
+            AppMode::Breakpoints => "Breakpoints",
+            AppMode::Prompt => "Prompt",
         };
 
         let header_text = format!("rustmaton - Game of Life (mode: {mode_label})");
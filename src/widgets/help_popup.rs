@@ -4,36 +4,7 @@ use ratatui::widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap};
 use crate::app::App;
 use crate::theme::Theme;
 
-const HELP_TEXT: &str = r#"
-# command keys:
-a       - toggle cursor point alive
-b       - move cursor to the beginning of the current line
-c       - clear the screen
-d       - toggle cursor point dead
-e       - move cursor to the end of the current line
-g       - enter pattern gallery mode
-h       - display help, or exit help if currently rendered
-l       - print the previous pattern again
-p       - cycle through the pattern classes defined in patterns.json
-q       - quit
-r       - rotate the current shape 90 degrees
-s       - toggle the simulation run loop
-' '     - step the simulation forward
-+       - speed up the simulation
--       - slow down the simulation
-[esc]   - exit help
-ctrl+c  - quit
-
-# gallery mode (press 'g' to enter):
-↑/↓     - navigate up/down in pattern tree
-←       - collapse type or go to parent
-→       - expand type or enter children
-[enter] - select pattern (sets as last pattern)
-g/[esc] - exit gallery mode
-"#;
-
 pub struct HelpPopup<'a> {
-    #[allow(dead_code)]
     app: &'a App,
     theme: &'a Theme,
 }
@@ -55,7 +26,7 @@ impl Widget for HelpPopup<'_> {
             .border_type(BorderType::Rounded)
             .border_style(self.theme.border);
 
-        let help_text = Paragraph::new(HELP_TEXT)
+        let help_text = Paragraph::new(self.app.keymap.help_text())
             .block(block)
             .wrap(Wrap { trim: false })
             .style(self.theme.cell_alive);
@@ -1,5 +1,8 @@
+pub mod breakpoints_panel;
+pub mod census_panel;
 pub mod footer_bar;
 pub mod game_canvas;
 pub mod header_bar;
 pub mod help_popup;
 pub mod pattern_gallery;
+pub mod population_chart;
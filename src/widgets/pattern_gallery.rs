@@ -2,6 +2,7 @@ use ratatui::prelude::*;
 use ratatui::widgets::{Block, List, ListItem, ListState};
 
 use crate::app::{App, AppMode};
+use crate::fuzzy::fuzzy_match;
 use crate::theme::Theme;
 
 /// A tree node in the flattened list
@@ -11,6 +12,8 @@ pub struct GalleryNode {
     pub type_idx: usize,
     pub pattern_idx: Option<usize>,
     pub is_selected_last: bool,
+    /// Character positions in `text` matched by the search
+    pub matched: Vec<usize>,
 }
 
 pub struct PatternGallery<'a> {
@@ -25,42 +28,7 @@ impl<'a> PatternGallery<'a> {
 
     /// Build a flat list of nodes from the tree structure
     pub fn build_nodes(&self) -> Vec<GalleryNode> {
-        let mut nodes = Vec::new();
-
-        for (type_idx, pattern_type) in self.app.configuration.iter().enumerate() {
-            let expanded = self
-                .app
-                .gallery_cursor
-                .expanded_types
-                .get(type_idx)
-                .copied()
-                .unwrap_or(true);
-
-            let indicator = if expanded { "▼" } else { "▶" };
-            nodes.push(GalleryNode {
-                text: format!("{} {}", indicator, pattern_type.name),
-                type_idx,
-                pattern_idx: None,
-                is_selected_last: false,
-            });
-
-            if expanded {
-                for (pat_idx, pattern) in pattern_type.patterns.iter().enumerate() {
-                    let is_selected_last = self.app.last_pattern == Some(pat_idx)
-                        && self.app.current_pattern_type == type_idx;
-
-                    let marker = if is_selected_last { "*" } else { " " };
-                    nodes.push(GalleryNode {
-                        text: format!("  {} {}", marker, pattern.name),
-                        type_idx,
-                        pattern_idx: Some(pat_idx),
-                        is_selected_last,
-                    });
-                }
-            }
-        }
-
-        nodes
+        flatten(self.app, true)
     }
 
     /// Find the list index for the current gallery cursor position
@@ -80,7 +48,19 @@ impl<'a> PatternGallery<'a> {
                 if node.is_selected_last {
                     style = style.fg(Color::Yellow);
                 }
-                ListItem::new(Line::from(node.text.clone()).style(style))
+                let spans: Vec<Span> = node
+                    .text
+                    .chars()
+                    .enumerate()
+                    .map(|(i, c)| {
+                        if node.matched.contains(&i) {
+                            Span::styled(c.to_string(), self.theme.search_match)
+                        } else {
+                            Span::raw(c.to_string())
+                        }
+                    })
+                    .collect();
+                ListItem::new(Line::from(spans).style(style))
             })
             .collect()
     }
@@ -91,7 +71,8 @@ impl StatefulWidget for PatternGallery<'_> {
 
     fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
         let nodes = self.build_nodes();
-        let is_gallery_mode = self.app.mode == AppMode::PatternGallery;
+        let is_gallery_mode =
+            self.app.mode == AppMode::PatternGallery || self.app.gallery_search().is_some();
 
         // Sync list state with gallery cursor when in gallery mode
         if is_gallery_mode {
@@ -113,7 +94,10 @@ impl StatefulWidget for PatternGallery<'_> {
         let list = List::new(items)
             .block(
                 Block::bordered()
-                    .title(" Patterns ")
+                    .title(match self.app.gallery_search() {
+                        Some(query) => format!(" Patterns /{query} "),
+                        None => String::from(" Patterns "),
+                    })
                     .border_style(self.theme.border),
             )
             .highlight_style(highlight_style)
@@ -125,6 +109,35 @@ impl StatefulWidget for PatternGallery<'_> {
 
 /// Helper to compute visible nodes for navigation
 pub fn compute_visible_nodes(app: &App) -> Vec<GalleryNode> {
+    flatten(app, false)
+}
+
+/// Nodes matching a search, best first: types by their best match, and
+/// within each type its patterns by score. A type whose name matches keeps
+/// all of its patterns.
+pub fn search_nodes(app: &App, query: &str) -> Vec<GalleryNode> {
+    search(app, query, false)
+}
+
+/// Text of a pattern node: the name, marked with `*` if it was placed last
+fn pattern_text(name: &str, is_selected_last: bool, show_marker: bool) -> String {
+    let marker = if show_marker && is_selected_last {
+        "*"
+    } else {
+        " "
+    };
+    format!("  {} {}", marker, name)
+}
+
+/// Characters before the name of a type ("▼ ") or pattern ("  * ")
+const TYPE_PREFIX: usize = 2;
+const PATTERN_PREFIX: usize = 4;
+
+fn flatten(app: &App, show_marker: bool) -> Vec<GalleryNode> {
+    if let Some(query) = app.gallery_search().filter(|q| !q.trim().is_empty()) {
+        return search(app, query, show_marker);
+    }
+
     let mut nodes = Vec::new();
 
     for (type_idx, pattern_type) in app.configuration.iter().enumerate() {
@@ -141,6 +154,7 @@ pub fn compute_visible_nodes(app: &App) -> Vec<GalleryNode> {
             type_idx,
             pattern_idx: None,
             is_selected_last: false,
+            matched: Vec::new(),
         });
 
         if expanded {
@@ -149,10 +163,11 @@ pub fn compute_visible_nodes(app: &App) -> Vec<GalleryNode> {
                     app.last_pattern == Some(pat_idx) && app.current_pattern_type == type_idx;
 
                 nodes.push(GalleryNode {
-                    text: format!("    {}", pattern.name),
+                    text: pattern_text(&pattern.name, is_selected_last, show_marker),
                     type_idx,
                     pattern_idx: Some(pat_idx),
                     is_selected_last,
+                    matched: Vec::new(),
                 });
             }
         }
@@ -160,3 +175,68 @@ pub fn compute_visible_nodes(app: &App) -> Vec<GalleryNode> {
 
     nodes
 }
+
+fn search(app: &App, query: &str, show_marker: bool) -> Vec<GalleryNode> {
+    let mut groups = Vec::new();
+
+    for (type_idx, pattern_type) in app.configuration.iter().enumerate() {
+        let type_match = fuzzy_match(query, &pattern_type.name);
+
+        let mut patterns: Vec<(i64, usize, Vec<usize>)> = pattern_type
+            .patterns
+            .iter()
+            .enumerate()
+            .filter_map(
+                |(pat_idx, pattern)| match fuzzy_match(query, &pattern.name) {
+                    Some(m) => Some((m.score, pat_idx, m.positions)),
+                    None => type_match.as_ref().map(|t| (t.score, pat_idx, Vec::new())),
+                },
+            )
+            .collect();
+        if type_match.is_none() && patterns.is_empty() {
+            continue;
+        }
+        patterns.sort_by_key(|(score, _, _)| std::cmp::Reverse(*score));
+
+        let best = patterns
+            .iter()
+            .map(|(score, _, _)| *score)
+            .chain(type_match.as_ref().map(|t| t.score))
+            .max()
+            .unwrap_or(0);
+        groups.push((best, type_idx, type_match, patterns));
+    }
+    groups.sort_by_key(|(best, _, _, _)| std::cmp::Reverse(*best));
+
+    let mut nodes = Vec::new();
+    for (_, type_idx, type_match, patterns) in groups {
+        let pattern_type = &app.configuration[type_idx];
+        nodes.push(GalleryNode {
+            text: format!("▼ {}", pattern_type.name),
+            type_idx,
+            pattern_idx: None,
+            is_selected_last: false,
+            matched: type_match
+                .map(|m| m.positions.iter().map(|p| p + TYPE_PREFIX).collect())
+                .unwrap_or_default(),
+        });
+
+        for (_, pat_idx, positions) in patterns {
+            let is_selected_last =
+                app.last_pattern == Some(pat_idx) && app.current_pattern_type == type_idx;
+            nodes.push(GalleryNode {
+                text: pattern_text(
+                    &pattern_type.patterns[pat_idx].name,
+                    is_selected_last,
+                    show_marker,
+                ),
+                type_idx,
+                pattern_idx: Some(pat_idx),
+                is_selected_last,
+                matched: positions.iter().map(|p| p + PATTERN_PREFIX).collect(),
+            });
+        }
+    }
+
+    nodes
+}
@@ -0,0 +1,107 @@
+use ratatui::prelude::*;
+use ratatui::widgets::{Axis, Block, Chart, Dataset, GraphType};
+
+use crate::app::App;
+use crate::theme::Theme;
+
+/// Panel beneath the canvas plotting the population over the run
+pub struct PopulationChart<'a> {
+    app: &'a App,
+    theme: &'a Theme,
+}
+
+impl<'a> PopulationChart<'a> {
+    pub fn new(app: &'a App, theme: &'a Theme) -> Self {
+        Self { app, theme }
+    }
+}
+
+fn point((generation, population): (u64, usize)) -> (f64, f64) {
+    (generation as f64, population as f64)
+}
+
+impl Widget for PopulationChart<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let history = &self.app.simulation.history;
+        let block = Block::bordered().border_style(self.theme.border);
+
+        let (Some(min), Some(max), Some(last)) = (history.min(), history.max(), history.last())
+        else {
+            block.title(" Population ").render(area, buf);
+            return;
+        };
+
+        // Braille markers give two points per column
+        let series: Vec<(f64, f64)> = history
+            .sample(area.width as usize * 2)
+            .into_iter()
+            .map(point)
+            .collect();
+        let min_marker = [point(min)];
+        let max_marker = [point(max)];
+        let current_marker = [point(last)];
+
+        let first_generation = series.first().map(|p| p.0).unwrap_or(0.0);
+        let last_generation = (last.0 as f64).max(first_generation + 1.0);
+        let top = (max.1 as f64).max(1.0);
+
+        let datasets = vec![
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(self.theme.chart_line)
+                .data(&series),
+            Dataset::default()
+                .marker(symbols::Marker::Dot)
+                .graph_type(GraphType::Scatter)
+                .style(self.theme.chart_min)
+                .data(&min_marker),
+            Dataset::default()
+                .marker(symbols::Marker::Dot)
+                .graph_type(GraphType::Scatter)
+                .style(self.theme.chart_max)
+                .data(&max_marker),
+            Dataset::default()
+                .marker(symbols::Marker::Block)
+                .graph_type(GraphType::Scatter)
+                .style(self.theme.chart_current)
+                .data(&current_marker),
+        ];
+
+        let title = Line::from(vec![
+            Span::raw(" Population "),
+            Span::styled(
+                format!("min {} @ gen {} ", min.1, min.0),
+                self.theme.chart_min,
+            ),
+            Span::styled(
+                format!("max {} @ gen {} ", max.1, max.0),
+                self.theme.chart_max,
+            ),
+            Span::styled(
+                format!("now {} @ gen {} ", last.1, last.0),
+                self.theme.chart_current,
+            ),
+        ]);
+
+        let chart = Chart::new(datasets)
+            .block(block.title(title))
+            .x_axis(
+                Axis::default()
+                    .style(self.theme.grid_boundary)
+                    .bounds([first_generation, last_generation])
+                    .labels(vec![
+                        format!("{first_generation}"),
+                        format!("{last_generation}"),
+                    ]),
+            )
+            .y_axis(
+                Axis::default()
+                    .style(self.theme.grid_boundary)
+                    .bounds([0.0, top])
+                    .labels(vec![String::from("0"), format!("{top}")]),
+            );
+
+        chart.render(area, buf);
+    }
+}
@@ -0,0 +1,277 @@
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, SendError, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::breakpoint::Breakpoints;
+use crate::simulation::{Simulation, Snapshot};
+
+/// Shortest time between updates published while a run continues
+const SNAPSHOT_INTERVAL: Duration = Duration::from_millis(33);
+/// Longest sleep between checks for cancellation while waiting out the delay
+const WAIT_SLICE: Duration = Duration::from_millis(5);
+
+/// How far a run goes
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    /// Keep going at the pace set by the run loop until cancelled
+    Continuous,
+    /// Step as fast as possible until the generation is reached
+    Generation(u64),
+}
+
+/// Everything the worker needs to advance the simulation on its own
+pub struct Job {
+    pub simulation: Simulation,
+    pub breakpoints: Breakpoints,
+    pub pause_when_stable: bool,
+    pub goal: Goal,
+}
+
+pub enum StopReason {
+    Cancelled,
+    /// Id of the breakpoint that fired
+    Breakpoint(u64),
+    Stable,
+    Reached,
+}
+
+pub enum Update {
+    /// The state to draw while the run continues
+    Snapshot {
+        snapshot: Box<Snapshot>,
+        /// Generations computed since the previous update
+        generations: u64,
+        became_stable: bool,
+    },
+    /// How far a jump has got; its state is only sent once it stops
+    Progress {
+        generation: u64,
+        /// Generations computed since the previous update
+        generations: u64,
+    },
+    /// The run ended and hands the simulation back
+    Stopped {
+        simulation: Box<Simulation>,
+        breakpoints: Breakpoints,
+        generations: u64,
+        became_stable: bool,
+        reason: StopReason,
+    },
+}
+
+/// Settings the run loop can change while a run is in progress
+#[derive(Default)]
+struct Controls {
+    cancel: AtomicBool,
+    delay_ms: AtomicU64,
+    gens_per_frame: AtomicU32,
+    /// Visible rows the elementary engine draws its history into
+    window_top: AtomicUsize,
+    window_rows: AtomicUsize,
+}
+
+/// Background thread that owns the simulation while it runs, so a slow
+/// generation never blocks input or drawing
+pub struct Worker {
+    jobs: Sender<Job>,
+    updates: Receiver<Update>,
+    controls: Arc<Controls>,
+    /// Goal of the run in progress, if any
+    goal: Option<Goal>,
+}
+
+impl Worker {
+    pub fn spawn() -> Self {
+        let (jobs, job_receiver) = mpsc::channel::<Job>();
+        let (update_sender, updates) = mpsc::channel();
+        let controls = Arc::new(Controls::default());
+
+        let worker_controls = Arc::clone(&controls);
+        thread::spawn(move || {
+            for job in job_receiver {
+                if run(job, &worker_controls, &update_sender).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            jobs,
+            updates,
+            controls,
+            goal: None,
+        }
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.goal.is_some()
+    }
+
+    pub fn goal(&self) -> Option<Goal> {
+        self.goal
+    }
+
+    pub fn start(&mut self, job: Job) -> io::Result<()> {
+        self.controls.cancel.store(false, Ordering::Relaxed);
+        self.goal = Some(job.goal);
+        self.jobs
+            .send(job)
+            .map_err(|_| io::Error::other("simulation worker stopped"))
+    }
+
+    /// Stop the run after the generation in progress
+    pub fn cancel(&self) {
+        self.controls.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn set_pace(&self, delay_ms: u128, gens_per_frame: u32) {
+        let delay_ms = delay_ms.min(u64::MAX as u128) as u64;
+        self.controls.delay_ms.store(delay_ms, Ordering::Relaxed);
+        self.controls
+            .gens_per_frame
+            .store(gens_per_frame, Ordering::Relaxed);
+    }
+
+    pub fn set_window(&self, top: usize, rows: usize) {
+        self.controls.window_top.store(top, Ordering::Relaxed);
+        self.controls.window_rows.store(rows, Ordering::Relaxed);
+    }
+
+    /// The next update from the worker, if one is waiting
+    pub fn try_recv(&mut self) -> io::Result<Option<Update>> {
+        match self.updates.try_recv() {
+            Ok(update) => {
+                if let Update::Stopped { .. } = update {
+                    self.goal = None;
+                }
+                Ok(Some(update))
+            }
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(io::Error::other("simulation worker stopped")),
+        }
+    }
+}
+
+/// What happened since the last update sent to the run loop
+struct Progress<'a> {
+    updates: &'a Sender<Update>,
+    goal: Goal,
+    last_snapshot: Instant,
+    /// Last generation of the history the run loop has; later values are
+    /// sent with the next snapshot
+    shown_generation: Option<u64>,
+    generations: u64,
+    became_stable: bool,
+}
+
+impl Progress<'_> {
+    fn snapshot_due(&self) -> bool {
+        self.generations > 0 && self.last_snapshot.elapsed() >= SNAPSHOT_INTERVAL
+    }
+
+    fn publish(&mut self, simulation: &Simulation) -> Result<(), SendError<Update>> {
+        self.last_snapshot = Instant::now();
+        // A jump skips drawing, so copying its state would only slow it down
+        if let Goal::Generation(_) = self.goal {
+            let update = Update::Progress {
+                generation: simulation.generation,
+                generations: self.generations,
+            };
+            self.generations = 0;
+            return self.updates.send(update);
+        }
+        let update = Update::Snapshot {
+            snapshot: Box::new(simulation.snapshot(self.shown_generation)),
+            generations: self.generations,
+            became_stable: self.became_stable,
+        };
+        self.shown_generation = Some(simulation.generation);
+        self.generations = 0;
+        self.became_stable = false;
+        self.updates.send(update)
+    }
+}
+
+/// Advance the simulation until the goal is reached, a breakpoint fires,
+/// it becomes periodic with auto-pause on, or the run is cancelled
+fn run(job: Job, controls: &Controls, updates: &Sender<Update>) -> Result<(), SendError<Update>> {
+    let Job {
+        mut simulation,
+        mut breakpoints,
+        pause_when_stable,
+        goal,
+    } = job;
+    let mut progress = Progress {
+        updates,
+        goal,
+        last_snapshot: Instant::now(),
+        shown_generation: simulation.history.last().map(|(generation, _)| generation),
+        generations: 0,
+        became_stable: false,
+    };
+
+    let reason = 'run: loop {
+        let batch = match goal {
+            Goal::Continuous => controls.gens_per_frame.load(Ordering::Relaxed).max(1),
+            Goal::Generation(_) => u32::MAX,
+        };
+        for _ in 0..batch {
+            if let Goal::Generation(target) = goal {
+                if simulation.generation >= target {
+                    break 'run StopReason::Reached;
+                }
+            }
+            if controls.cancel.load(Ordering::Relaxed) {
+                break 'run StopReason::Cancelled;
+            }
+
+            simulation.elementary.set_window(
+                controls.window_top.load(Ordering::Relaxed),
+                controls.window_rows.load(Ordering::Relaxed),
+            );
+            let was_stable = simulation.period.stability().is_some();
+            simulation.step();
+            progress.generations += 1;
+            if let Some(fired) = breakpoints.check(&simulation) {
+                break 'run StopReason::Breakpoint(fired);
+            }
+            if !was_stable && simulation.period.stability().is_some() {
+                progress.became_stable = true;
+                if pause_when_stable {
+                    break 'run StopReason::Stable;
+                }
+            }
+            if progress.snapshot_due() {
+                progress.publish(&simulation)?;
+            }
+        }
+
+        // Wait out the delay before the next batch, still publishing the
+        // last one once a frame is due
+        let delay = match goal {
+            Goal::Continuous => Duration::from_millis(controls.delay_ms.load(Ordering::Relaxed)),
+            Goal::Generation(_) => Duration::ZERO,
+        };
+        let waiting = Instant::now();
+        while waiting.elapsed() < delay {
+            if controls.cancel.load(Ordering::Relaxed) {
+                break 'run StopReason::Cancelled;
+            }
+            if progress.snapshot_due() {
+                progress.publish(&simulation)?;
+            }
+            thread::sleep(WAIT_SLICE.min(delay.saturating_sub(waiting.elapsed())));
+        }
+    };
+
+    updates.send(Update::Stopped {
+        simulation: Box::new(simulation),
+        breakpoints,
+        generations: progress.generations,
+        became_stable: progress.became_stable,
+        reason,
+    })
+}